@@ -0,0 +1,55 @@
+pub const DIVIDED_CLOCK: u32 = 6_000_000;
+pub const FAST_CLOCK: u32 = 30_000_000;
+
+pub fn frequency_range(high_speed: bool) -> (u32, u32) {
+    match high_speed {
+        false => (DIVIDED_CLOCK.div_ceil(0x10000), DIVIDED_CLOCK),
+        true => (DIVIDED_CLOCK.div_ceil(0x10000), FAST_CLOCK),
+    }
+}
+
+pub fn clock_divisor(high_speed: bool, frequency: u32) -> (u16, Option<bool>) {
+    let divisor = |base: u32| (base.div_ceil(frequency.max(1)) - 1).min(u16::MAX as u32) as u16;
+
+    match high_speed {
+        false => (divisor(DIVIDED_CLOCK), None),
+        true if frequency <= DIVIDED_CLOCK => (divisor(DIVIDED_CLOCK), Some(true)),
+        true => (divisor(FAST_CLOCK), Some(false)),
+    }
+}
+
+pub fn actual_frequency(divisor: u16, clkdiv: Option<bool>) -> u32 {
+    let base = match clkdiv {
+        Some(false) => FAST_CLOCK,
+        _ => DIVIDED_CLOCK,
+    };
+
+    base / (divisor as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divisor_rounds_down_the_frequency() {
+        for high_speed in [false, true] {
+            let (min, max) = frequency_range(high_speed);
+            let frequencies = [min, 100_000, 400_000, 1_000_000, 7_000_000, 13_000_000, max];
+            for frequency in frequencies.into_iter().filter(|&hz| hz <= max) {
+                let (divisor, clkdiv) = clock_divisor(high_speed, frequency);
+                let actual = actual_frequency(divisor, clkdiv);
+                assert!(actual <= frequency, "{frequency} Hz -> {actual} Hz");
+            }
+        }
+
+        assert_eq!(clock_divisor(true, 7_000_000), (4, Some(false)));
+        assert_eq!(clock_divisor(false, 1_000_000), (5, None));
+    }
+
+    #[test]
+    fn divisor_saturates() {
+        assert_eq!(clock_divisor(false, 0), (u16::MAX, None));
+        assert_eq!(clock_divisor(true, 1), (u16::MAX, Some(true)));
+    }
+}
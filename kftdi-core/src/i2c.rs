@@ -0,0 +1,93 @@
+use alloc::vec::Vec;
+
+use crate::mpsse::{
+    ReadBitsPosMsb, ReadBytesPosMsb, SetDataBitsLowByte, WriteBitsNegMsb, WriteBytesNegMsb,
+};
+
+pub const SCL: u8 = 1 << 0;
+pub const SDA_OUT: u8 = 1 << 1;
+pub const SDA_IN: u8 = 1 << 2;
+
+const PIN_REPEAT: usize = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PinBase {
+    pub value: u8,
+    pub direction: u8,
+    /// Tristate lines that should be high instead of driving them. This is
+    /// not true open-drain: data phases still drive SDA push-pull and SCL is
+    /// driven by the clock engine.
+    pub release_high_lines: bool,
+}
+
+pub fn push_pins(cmd: &mut Vec<u8>, base: PinBase, value: u8, direction: u8) {
+    // Release high lines by making them inputs and let the pull-ups do the
+    // rest.
+    let (value, direction) = match base.release_high_lines {
+        true => (0, direction & !value),
+        false => (value, direction),
+    };
+
+    for _ in 0..PIN_REPEAT {
+        cmd.extend_from_slice(&[
+            SetDataBitsLowByte::byte(),
+            base.value | value,
+            base.direction | direction,
+        ]);
+    }
+}
+
+pub fn push_start(cmd: &mut Vec<u8>, base: PinBase) {
+    push_pins(cmd, base, SDA_OUT, SCL | SDA_OUT);
+    push_pins(cmd, base, SCL | SDA_OUT, SCL | SDA_OUT);
+    push_pins(cmd, base, SCL, SCL | SDA_OUT);
+    push_pins(cmd, base, 0, SCL | SDA_OUT);
+}
+
+pub fn push_stop(cmd: &mut Vec<u8>, base: PinBase) {
+    push_pins(cmd, base, 0, SCL | SDA_OUT);
+    push_pins(cmd, base, SCL, SCL | SDA_OUT);
+    push_pins(cmd, base, SCL | SDA_OUT, SCL | SDA_OUT);
+}
+
+pub fn push_write_byte(cmd: &mut Vec<u8>, base: PinBase, byte: u8) {
+    push_pins(cmd, base, 0, SCL | SDA_OUT);
+    cmd.extend_from_slice(&[WriteBytesNegMsb::byte(), 0x00, 0x00, byte]);
+    push_pins(cmd, base, 0, SCL);
+    cmd.extend_from_slice(&[ReadBitsPosMsb::byte(), 0x00]);
+}
+
+pub fn push_read_byte(cmd: &mut Vec<u8>, base: PinBase, ack: bool) {
+    push_pins(cmd, base, 0, SCL);
+    cmd.extend_from_slice(&[ReadBytesPosMsb::byte(), 0x00, 0x00]);
+    push_pins(cmd, base, 0, SCL | SDA_OUT);
+    cmd.extend_from_slice(&[WriteBitsNegMsb::byte(), 0x00, if ack { 0x00 } else { 0xff }]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_high_lines_tristates_high_bits() {
+        let base = PinBase {
+            value: 1 << 4,
+            direction: 1 << 4,
+            release_high_lines: true,
+        };
+
+        let mut cmd = Vec::new();
+        push_pins(&mut cmd, base, SDA_OUT, SCL | SDA_OUT);
+        assert_eq!(
+            cmd[..3],
+            [SetDataBitsLowByte::byte(), 1 << 4, (1 << 4) | SCL]
+        );
+
+        cmd.clear();
+        push_pins(&mut cmd, PinBase::default(), SDA_OUT, SCL | SDA_OUT);
+        assert_eq!(
+            cmd[..3],
+            [SetDataBitsLowByte::byte(), SDA_OUT, SCL | SDA_OUT]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use alloc::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TapState {
+    TestLogicReset,
+    RunTestIdle,
+    SelectDrScan,
+    CaptureDr,
+    ShiftDr,
+    Exit1Dr,
+    PauseDr,
+    Exit2Dr,
+    UpdateDr,
+    SelectIrScan,
+    CaptureIr,
+    ShiftIr,
+    Exit1Ir,
+    PauseIr,
+    Exit2Ir,
+    UpdateIr,
+}
+
+impl TapState {
+    pub fn next(self, tms: bool) -> Self {
+        use TapState::*;
+
+        match (self, tms) {
+            (TestLogicReset, false) => RunTestIdle,
+            (TestLogicReset, true) => TestLogicReset,
+            (RunTestIdle, false) => RunTestIdle,
+            (RunTestIdle, true) => SelectDrScan,
+            (SelectDrScan, false) => CaptureDr,
+            (SelectDrScan, true) => SelectIrScan,
+            (CaptureDr, false) | (ShiftDr, false) | (Exit2Dr, false) => ShiftDr,
+            (CaptureDr, true) | (ShiftDr, true) => Exit1Dr,
+            (Exit1Dr, false) | (PauseDr, false) => PauseDr,
+            (Exit1Dr, true) | (Exit2Dr, true) => UpdateDr,
+            (PauseDr, true) => Exit2Dr,
+            (UpdateDr, false) | (UpdateIr, false) => RunTestIdle,
+            (UpdateDr, true) | (UpdateIr, true) => SelectDrScan,
+            (SelectIrScan, false) => CaptureIr,
+            (SelectIrScan, true) => TestLogicReset,
+            (CaptureIr, false) | (ShiftIr, false) | (Exit2Ir, false) => ShiftIr,
+            (CaptureIr, true) | (ShiftIr, true) => Exit1Ir,
+            (Exit1Ir, false) | (PauseIr, false) => PauseIr,
+            (Exit1Ir, true) | (Exit2Ir, true) => UpdateIr,
+            (PauseIr, true) => Exit2Ir,
+        }
+    }
+
+    pub fn path(self, to: TapState) -> (u8, u8) {
+        if to == TapState::TestLogicReset {
+            return (0x1f, 5);
+        }
+
+        let mut seen = [false; 16];
+        let mut queue = VecDeque::from([(self, 0u8, 0u8)]);
+        seen[self as usize] = true;
+
+        while let Some((state, tms, count)) = queue.pop_front() {
+            if state == to {
+                return (tms, count);
+            }
+
+            for bit in [false, true] {
+                let next = state.next(bit);
+                if !seen[next as usize] {
+                    seen[next as usize] = true;
+                    queue.push_back((next, tms | ((bit as u8) << count), count + 1));
+                }
+            }
+        }
+
+        (0, 0)
+    }
+}
@@ -0,0 +1,9 @@
+#![no_std]
+
+extern crate alloc;
+
+pub mod clock;
+pub mod i2c;
+pub mod jtag;
+pub mod mpsse;
+pub mod spi;
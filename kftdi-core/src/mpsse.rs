@@ -0,0 +1,716 @@
+use alloc::vec::Vec;
+
+pub const MAX_CLOCK_BYTES: usize = 65536;
+pub const RTCK: u8 = 1 << 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockEdge {
+    Rising,
+    Falling,
+}
+
+impl ClockEdge {
+    pub fn opposite(self) -> Self {
+        match self {
+            ClockEdge::Rising => ClockEdge::Falling,
+            ClockEdge::Falling => ClockEdge::Rising,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+pub fn shift_opcode(
+    write: Option<ClockEdge>,
+    read: Option<ClockEdge>,
+    order: BitOrder,
+    bits: bool,
+) -> u8 {
+    let mut op = 0;
+
+    if let Some(edge) = write {
+        op |= 0x10;
+        if edge == ClockEdge::Falling {
+            op |= 0x01;
+        }
+    }
+
+    if let Some(edge) = read {
+        op |= 0x20;
+        if edge == ClockEdge::Falling {
+            op |= 0x04;
+        }
+    }
+
+    if order == BitOrder::LsbFirst {
+        op |= 0x08;
+    }
+
+    if bits {
+        op |= 0x02;
+    }
+
+    op
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MpsseCmdBuilder {
+    cmd: Vec<u8>,
+    read_len: usize,
+}
+
+impl MpsseCmdBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.cmd
+    }
+
+    pub fn read_len(&self) -> usize {
+        self.read_len
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.cmd
+    }
+
+    pub fn set_low_data_bits(mut self, value: u8, direction: u8) -> Self {
+        self.cmd
+            .extend_from_slice(&[SetDataBitsLowByte::byte(), value, direction]);
+        self
+    }
+
+    pub fn set_high_data_bits(mut self, value: u8, direction: u8) -> Self {
+        self.cmd
+            .extend_from_slice(&[SetDataBitsHighByte::byte(), value, direction]);
+        self
+    }
+
+    pub fn get_low_data_bits(mut self) -> Self {
+        self.cmd.push(GetDataBitsLowByte::byte());
+        self.read_len += 1;
+        self
+    }
+
+    pub fn get_high_data_bits(mut self) -> Self {
+        self.cmd.push(GetDataBitsHighByte::byte());
+        self.read_len += 1;
+        self
+    }
+
+    pub fn clock_bytes_out(mut self, edge: ClockEdge, order: BitOrder, data: &[u8]) -> Self {
+        let op = shift_opcode(Some(edge), None, order, false);
+
+        for chunk in data.chunks(MAX_CLOCK_BYTES) {
+            self.cmd.push(op);
+            self.cmd
+                .extend_from_slice(&((chunk.len() - 1) as u16).to_le_bytes());
+            self.cmd.extend_from_slice(chunk);
+        }
+
+        self
+    }
+
+    pub fn clock_bytes_in(mut self, edge: ClockEdge, order: BitOrder, len: usize) -> Self {
+        let op = shift_opcode(None, Some(edge), order, false);
+
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk_len = remaining.min(MAX_CLOCK_BYTES);
+            self.cmd.push(op);
+            self.cmd
+                .extend_from_slice(&((chunk_len - 1) as u16).to_le_bytes());
+            remaining -= chunk_len;
+        }
+
+        self.read_len += len;
+        self
+    }
+
+    pub fn clock_bytes_inout(mut self, out_edge: ClockEdge, order: BitOrder, data: &[u8]) -> Self {
+        let op = shift_opcode(Some(out_edge), Some(out_edge.opposite()), order, false);
+
+        for chunk in data.chunks(MAX_CLOCK_BYTES) {
+            self.cmd.push(op);
+            self.cmd
+                .extend_from_slice(&((chunk.len() - 1) as u16).to_le_bytes());
+            self.cmd.extend_from_slice(chunk);
+        }
+
+        self.read_len += data.len();
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `count` is not in `1..=8`.
+    pub fn clock_bits_out(mut self, edge: ClockEdge, order: BitOrder, data: u8, count: u8) -> Self {
+        assert!(
+            (1..=8).contains(&count),
+            "bit count must be 1..=8, got {}",
+            count
+        );
+        let op = shift_opcode(Some(edge), None, order, true);
+
+        self.cmd.extend_from_slice(&[op, count - 1, data]);
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `count` is not in `1..=8`.
+    pub fn clock_bits_in(mut self, edge: ClockEdge, order: BitOrder, count: u8) -> Self {
+        assert!(
+            (1..=8).contains(&count),
+            "bit count must be 1..=8, got {}",
+            count
+        );
+        let op = shift_opcode(None, Some(edge), order, true);
+
+        self.cmd.extend_from_slice(&[op, count - 1]);
+        self.read_len += 1;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `count` is not in `1..=8`.
+    pub fn clock_bits_inout(
+        mut self,
+        out_edge: ClockEdge,
+        order: BitOrder,
+        data: u8,
+        count: u8,
+    ) -> Self {
+        assert!(
+            (1..=8).contains(&count),
+            "bit count must be 1..=8, got {}",
+            count
+        );
+        let op = shift_opcode(Some(out_edge), Some(out_edge.opposite()), order, true);
+
+        self.cmd.extend_from_slice(&[op, count - 1, data]);
+        self.read_len += 1;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `count` is not in `1..=7`.
+    pub fn clock_tms_out(mut self, edge: ClockEdge, tms: u8, count: u8, tdi: bool) -> Self {
+        assert!(
+            (1..=7).contains(&count),
+            "tms count must be 1..=7, got {}",
+            count
+        );
+        let op = 0x40 | (shift_opcode(Some(edge), None, BitOrder::LsbFirst, true) & !0x10);
+
+        self.cmd
+            .extend_from_slice(&[op, count - 1, (tms & 0x7f) | ((tdi as u8) << 7)]);
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `count` is not in `1..=7`.
+    pub fn clock_tms_inout(mut self, out_edge: ClockEdge, tms: u8, count: u8, tdi: bool) -> Self {
+        assert!(
+            (1..=7).contains(&count),
+            "tms count must be 1..=7, got {}",
+            count
+        );
+        let op = 0x40
+            | (shift_opcode(
+                Some(out_edge),
+                Some(out_edge.opposite()),
+                BitOrder::LsbFirst,
+                true,
+            ) & !0x10);
+
+        self.cmd
+            .extend_from_slice(&[op, count - 1, (tms & 0x7f) | ((tdi as u8) << 7)]);
+        self.read_len += 1;
+        self
+    }
+
+    pub fn mcu_read_short(mut self, address: u8) -> Self {
+        self.cmd.extend_from_slice(&[McuReadShort::byte(), address]);
+        self.read_len += 1;
+        self
+    }
+
+    pub fn mcu_read_extended(mut self, address: u16) -> Self {
+        let [high, low] = address.to_be_bytes();
+        self.cmd
+            .extend_from_slice(&[McuReadExtended::byte(), high, low]);
+        self.read_len += 1;
+        self
+    }
+
+    pub fn mcu_write_short(mut self, address: u8, data: u8) -> Self {
+        self.cmd
+            .extend_from_slice(&[McuWriteShort::byte(), address, data]);
+        self
+    }
+
+    pub fn mcu_write_extended(mut self, address: u16, data: u8) -> Self {
+        let [high, low] = address.to_be_bytes();
+        self.cmd
+            .extend_from_slice(&[McuWriteExtended::byte(), high, low, data]);
+        self
+    }
+
+    pub fn wait_on_io_high(mut self) -> Self {
+        self.cmd.push(WaitOnIOHigh::byte());
+        self
+    }
+
+    pub fn wait_on_io_low(mut self) -> Self {
+        self.cmd.push(WaitOnIOLow::byte());
+        self
+    }
+
+    pub fn clock_until_io(mut self, high: bool) -> Self {
+        match high {
+            true => self.cmd.push(ClockUntilIOHigh::byte()),
+            false => self.cmd.push(ClockUntilIOLow::byte()),
+        }
+        self
+    }
+
+    pub fn delay_clocks(mut self, cycles: usize) -> Self {
+        let mut bytes = cycles / 8;
+        while bytes > 0 {
+            let chunk = bytes.min(MAX_CLOCK_BYTES);
+            self.cmd.push(DelayBytes::byte());
+            self.cmd
+                .extend_from_slice(&((chunk - 1) as u16).to_le_bytes());
+            bytes -= chunk;
+        }
+
+        if !cycles.is_multiple_of(8) {
+            self.cmd
+                .extend_from_slice(&[DelayBits::byte(), (cycles % 8 - 1) as u8]);
+        }
+        self
+    }
+
+    pub fn enable_loopback(mut self) -> Self {
+        self.cmd.push(EnableLoopback::byte());
+        self
+    }
+
+    pub fn disable_loopback(mut self) -> Self {
+        self.cmd.push(DisableLoopback::byte());
+        self
+    }
+
+    pub fn send_immediate(mut self) -> Self {
+        self.cmd.push(SendImmediate::byte());
+        self
+    }
+
+    pub fn request_immediate(self) -> Self {
+        let mut pos = 0;
+        let mut last = None;
+
+        while pos < self.cmd.len() {
+            match Command::decode(&self.cmd[pos..]) {
+                Some((command, len)) => {
+                    last = Some(command.byte());
+                    pos += len;
+                }
+                None => {
+                    last = None;
+                    pos += 1;
+                }
+            }
+        }
+
+        if last == Some(SendImmediate::byte()) {
+            self
+        } else {
+            self.send_immediate()
+        }
+    }
+}
+
+trait Field: Sized {
+    fn encode(&self, buf: &mut Vec<u8>);
+    fn decode(buf: &[u8]) -> Option<(Self, usize)>;
+}
+
+impl Field for u8 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self);
+    }
+
+    fn decode(buf: &[u8]) -> Option<(Self, usize)> {
+        Some((*buf.first()?, 1))
+    }
+}
+
+impl Field for u16 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(buf: &[u8]) -> Option<(Self, usize)> {
+        Some((u16::from_le_bytes([*buf.first()?, *buf.get(1)?]), 2))
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Payload(pub Vec<u8>);
+
+impl Field for Payload {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        assert!(
+            (1..=MAX_CLOCK_BYTES).contains(&self.0.len()),
+            "payload length must be between 1 and {} bytes",
+            MAX_CLOCK_BYTES
+        );
+        ((self.0.len() - 1) as u16).encode(buf);
+        buf.extend_from_slice(&self.0);
+    }
+
+    fn decode(buf: &[u8]) -> Option<(Self, usize)> {
+        let (length, pos) = u16::decode(buf)?;
+        let data = buf.get(pos..pos + length as usize + 1)?;
+
+        Some((Payload(data.to_vec()), pos + data.len()))
+    }
+}
+
+macro_rules! mpsse_commands {
+    ($($cmd: ident { cmd: $cmd_byte:literal$(,)?$($field_name:ident: $field_type:ty),* }),*$(,)?) => {
+        #[repr(u8)]
+        pub enum CommandByte {
+            $($cmd = $cmd_byte,)*
+        }
+
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        pub enum Command {
+            $($cmd($cmd),)*
+        }
+
+        impl Command {
+            pub fn byte(&self) -> u8 {
+                match self {
+                    $(Command::$cmd(_) => $cmd_byte,)*
+                }
+            }
+
+            pub fn encode(&self, buf: &mut Vec<u8>) {
+                match self {
+                    $(Command::$cmd(cmd) => cmd.encode(buf),)*
+                }
+            }
+
+            pub fn decode(buf: &[u8]) -> Option<(Self, usize)> {
+                match *buf.first()? {
+                    $($cmd_byte => $cmd::decode(buf).map(|(cmd, len)| (Command::$cmd(cmd), len)),)*
+                    _ => None,
+                }
+            }
+        }
+
+        $(
+            #[derive(Clone, Debug, Default, Eq, PartialEq)]
+            pub struct $cmd {
+                $(pub $field_name: $field_type,)*
+            }
+
+            impl $cmd {
+                pub fn byte() -> u8 {
+                    $cmd_byte
+                }
+
+                pub fn encode(&self, buf: &mut Vec<u8>) {
+                    buf.push($cmd_byte);
+                    $(Field::encode(&self.$field_name, buf);)*
+                }
+
+                #[allow(unused_mut)]
+                pub fn decode(buf: &[u8]) -> Option<(Self, usize)> {
+                    if *buf.first()? != $cmd_byte {
+                        return None;
+                    }
+
+                    let mut pos = 1;
+                    $(
+                        let ($field_name, len) = <$field_type as Field>::decode(&buf[pos..])?;
+                        pos += len;
+                    )*
+
+                    Some(($cmd { $($field_name,)* }, pos))
+                }
+            }
+
+            impl From<$cmd> for Command {
+                fn from(cmd: $cmd) -> Self {
+                    Command::$cmd(cmd)
+                }
+            }
+        )*
+    };
+}
+
+impl Command {
+    pub fn response_len(&self) -> usize {
+        match self {
+            Command::GetDataBitsLowByte(_)
+            | Command::GetDataBitsHighByte(_)
+            | Command::McuReadShort(_)
+            | Command::McuReadExtended(_) => 1,
+            Command::ReadBytesPosLsb(cmd) => cmd.length as usize + 1,
+            Command::ReadBytesNegLsb(cmd) => cmd.length as usize + 1,
+            Command::ReadBytesPosMsb(cmd) => cmd.length as usize + 1,
+            Command::ReadBytesNegMsb(cmd) => cmd.length as usize + 1,
+            Command::WriteBytesNegReadPosLsb(cmd) => cmd.data.0.len(),
+            Command::WriteBytesPosReadNegLsb(cmd) => cmd.data.0.len(),
+            Command::WriteBytesNegReadPosMsb(cmd) => cmd.data.0.len(),
+            Command::WriteBytesPosReadNegMsb(cmd) => cmd.data.0.len(),
+            Command::ReadBitsPosLsb(_)
+            | Command::ReadBitsNegLsb(_)
+            | Command::ReadBitsPosMsb(_)
+            | Command::ReadBitsNegMsb(_)
+            | Command::WriteBitsNegReadPosLsb(_)
+            | Command::WriteBitsPosReadNegLsb(_)
+            | Command::WriteBitsNegReadPosMsb(_)
+            | Command::WriteBitsPosReadNegMsb(_)
+            | Command::WriteTmsBitsPosReadPos(_)
+            | Command::WriteTmsBitsPosReadNeg(_)
+            | Command::WriteTmsBitsNegReadPos(_)
+            | Command::WriteTmsBitsNegReadNeg(_) => 1,
+            _ => 0,
+        }
+    }
+}
+
+mpsse_commands! {
+    SetDataBitsLowByte { cmd: 0x80, value: u8, direction: u8 },
+    GetDataBitsLowByte { cmd: 0x81 },
+    SetDataBitsHighByte { cmd: 0x82, value: u8, direction: u8 },
+    GetDataBitsHighByte { cmd: 0x83 },
+    EnableLoopback { cmd: 0x84 },
+    DisableLoopback { cmd: 0x85 },
+    SetClockFrequency { cmd: 0x86, divisor: u16 },
+    SendImmediate { cmd: 0x87 },
+    WaitOnIOHigh { cmd: 0x88 },
+    WaitOnIOLow { cmd: 0x89 },
+    DisableClockDivide { cmd: 0x8A },
+    EnableClockDivide { cmd: 0x8B },
+    Enable3PhaseClocking { cmd: 0x8C },
+    Disable3PhaseClocking { cmd: 0x8D },
+    DelayBits { cmd: 0x8E, length: u8 },
+    DelayBytes { cmd: 0x8F, length: u16 },
+    ClockUntilIOHigh { cmd: 0x94 },
+    ClockUntilIOLow { cmd: 0x95 },
+    EnableAdaptiveClocking { cmd: 0x96 },
+    DisableAdaptiveClocking { cmd: 0x97 },
+    EnableDriveOnlyZero { cmd: 0x9E, low: u8, high: u8 },
+
+    McuReadShort { cmd: 0x90, address: u8 },
+    McuReadExtended { cmd: 0x91, address_high: u8, address_low: u8 },
+    McuWriteShort { cmd: 0x92, address: u8, data: u8 },
+    McuWriteExtended { cmd: 0x93, address_high: u8, address_low: u8, data: u8 },
+
+    WriteBytesPosLsb { cmd: 0x18, data: Payload },
+    WriteBytesNegLsb { cmd: 0x19, data: Payload },
+    WriteBitsPosLsb { cmd: 0x1A, length: u8, byte: u8 },
+    WriteBitsNegLsb { cmd: 0x1B, length: u8, byte: u8 },
+    ReadBytesPosLsb { cmd: 0x28, length: u16 },
+    ReadBitsPosLsb { cmd: 0x2A, length: u8 },
+    ReadBytesNegLsb { cmd: 0x2C, length: u16 },
+    ReadBitsNegLsb { cmd: 0x2E, length: u8 },
+    WriteBytesNegReadPosLsb { cmd: 0x39, data: Payload },
+    WriteBitsNegReadPosLsb { cmd: 0x3B, length: u8, byte: u8 },
+    WriteBytesPosReadNegLsb { cmd: 0x3C, data: Payload },
+    WriteBitsPosReadNegLsb { cmd: 0x3E, length: u8, byte: u8 },
+
+    WriteBytesPosMsb { cmd: 0x10, data: Payload },
+    WriteBytesNegMsb { cmd: 0x11, data: Payload },
+    WriteBitsPosMsb { cmd: 0x12, length: u8, byte: u8 },
+    WriteBitsNegMsb { cmd: 0x13, length: u8, byte: u8 },
+    ReadBytesPosMsb { cmd: 0x20, length: u16 },
+    ReadBitsPosMsb { cmd: 0x22, length: u8 },
+    ReadBitsNegMsb { cmd: 0x26, length: u8 },
+    ReadBytesNegMsb { cmd: 0x24, length: u16 },
+    WriteBytesNegReadPosMsb { cmd: 0x31, data: Payload },
+    WriteBytesPosReadNegMsb { cmd: 0x34, data: Payload },
+    WriteBitsNegReadPosMsb { cmd: 0x33, length: u8, byte: u8 },
+    WriteBitsPosReadNegMsb { cmd: 0x36, length: u8, byte: u8 },
+
+    WriteTmsBitsPos { cmd: 0x4A, length: u8, byte: u8  },
+    WriteTmsBitsNeg { cmd: 0x4B, length: u8, byte: u8  },
+    WriteTmsBitsPosReadPos { cmd: 0x6A, length: u8, byte: u8 },
+    WriteTmsBitsPosReadNeg { cmd: 0x6E, length: u8, byte: u8 },
+    WriteTmsBitsNegReadPos { cmd: 0x6B, length: u8, byte: u8 },
+    WriteTmsBitsNegReadNeg { cmd: 0x6F, length: u8, byte: u8 },
+
+    Synchronize { cmd: 0xAB },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn round_trip_every_opcode() {
+        let mut decoded = 0;
+
+        for op in 0..=u8::MAX {
+            let buf = [op, 0x02, 0x00, 0x11, 0x22, 0x33, 0x44];
+            let Some((command, len)) = Command::decode(&buf) else {
+                continue;
+            };
+
+            let mut encoded = Vec::new();
+            command.encode(&mut encoded);
+
+            assert_eq!(command.byte(), op);
+            assert_eq!(encoded, &buf[..len], "opcode {:#04x}", op);
+            assert_eq!(Command::decode(&encoded), Some((command, len)));
+            decoded += 1;
+        }
+
+        assert_eq!(decoded, 56);
+    }
+
+    #[test]
+    fn encode_fields() {
+        let mut buf = Vec::new();
+        SetDataBitsLowByte {
+            value: 0x12,
+            direction: 0xfb,
+        }
+        .encode(&mut buf);
+        DelayBytes { length: 0x0102 }.encode(&mut buf);
+        McuReadExtended {
+            address_high: 0x10,
+            address_low: 0x20,
+        }
+        .encode(&mut buf);
+        WriteBytesPosMsb {
+            data: Payload(vec![0xaa, 0xbb]),
+        }
+        .encode(&mut buf);
+
+        assert_eq!(
+            buf,
+            [0x80, 0x12, 0xfb, 0x8f, 0x02, 0x01, 0x91, 0x10, 0x20, 0x10, 0x01, 0x00, 0xaa, 0xbb]
+        );
+    }
+
+    #[test]
+    fn decode_truncated() {
+        assert_eq!(Command::decode(&[]), None);
+        assert_eq!(Command::decode(&[0x00]), None);
+        assert_eq!(Command::decode(&[0x80, 0x12]), None);
+        assert_eq!(Command::decode(&[0x8f, 0x02]), None);
+        assert_eq!(Command::decode(&[0x10, 0x02, 0x00, 0xaa, 0xbb]), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn encode_empty_payload() {
+        WriteBytesPosMsb::default().encode(&mut Vec::new());
+    }
+
+    #[test]
+    fn response_len_matches_builder() {
+        let builder = MpsseCmdBuilder::new()
+            .set_low_data_bits(0x08, 0x0b)
+            .get_low_data_bits()
+            .clock_bytes_out(ClockEdge::Falling, BitOrder::MsbFirst, &[1, 2, 3])
+            .clock_bytes_in(ClockEdge::Rising, BitOrder::MsbFirst, 5)
+            .clock_bytes_inout(ClockEdge::Falling, BitOrder::LsbFirst, &[4, 5])
+            .clock_bits_in(ClockEdge::Rising, BitOrder::MsbFirst, 3)
+            .clock_tms_inout(ClockEdge::Falling, 0x03, 2, false)
+            .mcu_read_short(0x12)
+            .mcu_read_extended(0x0034)
+            .send_immediate();
+
+        let mut pos = 0;
+        let mut response_len = 0;
+        let cmd = builder.as_slice();
+        while pos < cmd.len() {
+            let (command, len) = Command::decode(&cmd[pos..]).unwrap();
+            response_len += command.response_len();
+            pos += len;
+        }
+
+        assert_eq!(response_len, builder.read_len());
+    }
+
+    #[test]
+    fn mcu_addressing() {
+        let cmd = MpsseCmdBuilder::new()
+            .mcu_read_short(0x10)
+            .mcu_read_extended(0x0010)
+            .mcu_write_short(0x20, 0xaa)
+            .mcu_write_extended(0x0020, 0xbb);
+
+        assert_eq!(
+            cmd.as_slice(),
+            [0x90, 0x10, 0x91, 0x00, 0x10, 0x92, 0x20, 0xaa, 0x93, 0x00, 0x20, 0xbb]
+        );
+        assert_eq!(cmd.read_len(), 2);
+    }
+
+    #[test]
+    fn request_immediate_once() {
+        let cmd = MpsseCmdBuilder::new()
+            .get_low_data_bits()
+            .request_immediate()
+            .request_immediate();
+
+        assert_eq!(cmd.as_slice(), [0x81, 0x87]);
+    }
+
+    #[test]
+    fn bit_count_bounds() {
+        let cmd = MpsseCmdBuilder::new()
+            .clock_bits_out(ClockEdge::Falling, BitOrder::MsbFirst, 0xaa, 1)
+            .clock_bits_in(ClockEdge::Rising, BitOrder::MsbFirst, 8)
+            .clock_tms_out(ClockEdge::Falling, 0x01, 1, false)
+            .clock_tms_inout(ClockEdge::Falling, 0x7f, 7, true);
+
+        assert_eq!(
+            cmd.as_slice(),
+            [0x13, 0x00, 0xaa, 0x22, 0x07, 0x4b, 0x00, 0x01, 0x6b, 0x06, 0xff]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_bit_count() {
+        MpsseCmdBuilder::new().clock_bits_out(ClockEdge::Falling, BitOrder::MsbFirst, 0, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn oversized_bit_count() {
+        MpsseCmdBuilder::new().clock_bits_inout(ClockEdge::Falling, BitOrder::MsbFirst, 0, 9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_tms_count() {
+        MpsseCmdBuilder::new().clock_tms_inout(ClockEdge::Falling, 0, 0, false);
+    }
+
+    #[test]
+    #[should_panic]
+    fn oversized_tms_count() {
+        MpsseCmdBuilder::new().clock_tms_out(ClockEdge::Falling, 0, 8, false);
+    }
+}
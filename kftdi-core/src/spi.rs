@@ -0,0 +1,42 @@
+use crate::mpsse::ClockEdge;
+
+pub const SCK: u8 = 1 << 0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpiMode {
+    #[default]
+    Mode0,
+    Mode1,
+    Mode2,
+    Mode3,
+}
+
+impl SpiMode {
+    pub fn cpol(self) -> bool {
+        matches!(self, SpiMode::Mode2 | SpiMode::Mode3)
+    }
+
+    pub fn cpha(self) -> bool {
+        matches!(self, SpiMode::Mode1 | SpiMode::Mode3)
+    }
+
+    pub fn out_edge(self) -> ClockEdge {
+        if self.cpol() == self.cpha() {
+            ClockEdge::Falling
+        } else {
+            ClockEdge::Rising
+        }
+    }
+
+    pub fn in_edge(self) -> ClockEdge {
+        self.out_edge().opposite()
+    }
+
+    pub fn idle(self) -> u8 {
+        if self.cpol() {
+            SCK
+        } else {
+            0
+        }
+    }
+}
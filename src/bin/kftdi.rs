@@ -0,0 +1,316 @@
+use clap::{command, value_parser, Arg, ArgAction, ArgMatches, Command};
+use tokio::io::AsyncWriteExt;
+
+use kftdi::gpio::Direction;
+use kftdi::i2c::I2cBus;
+use kftdi::mpsse::MpsseInterface;
+use kftdi::spi::{SpiBus, SpiMode};
+use kftdi::{Error, Interface, PortLetter, Result};
+
+const SPI_CS: u8 = 1 << 3;
+
+fn cli() -> Command {
+    command!()
+        .subcommand_required(true)
+        .arg(
+            Arg::new("serial")
+                .long("serial")
+                .short('s')
+                .global(true)
+                .help("serial number of the device to open"),
+        )
+        .arg(
+            Arg::new("port")
+                .long("port")
+                .short('p')
+                .global(true)
+                .default_value("A")
+                .value_parser(value_parser!(char))
+                .help("interface letter to open"),
+        )
+        .subcommand(Command::new("list").about("list connected devices"))
+        .subcommand(
+            Command::new("eeprom")
+                .about("read or write the configuration eeprom")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("dump")
+                        .arg(Arg::new("file").help("write the raw image to a file")),
+                )
+                .subcommand(Command::new("flash").arg(Arg::new("file").required(true))),
+        )
+        .subcommand(
+            Command::new("gpio")
+                .about("read or drive mpsse gpio pins")
+                .subcommand_required(true)
+                .subcommand(Command::new("get"))
+                .subcommand(
+                    Command::new("set")
+                        .arg(
+                            Arg::new("pin")
+                                .required(true)
+                                .value_parser(value_parser!(u8).range(0..16)),
+                        )
+                        .arg(
+                            Arg::new("level")
+                                .required(true)
+                                .value_parser(value_parser!(u8).range(0..2)),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("uart")
+                .about("serial port utilities")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("term").arg(
+                        Arg::new("baud")
+                            .long("baud")
+                            .short('b')
+                            .default_value("115200")
+                            .value_parser(value_parser!(u32)),
+                    ),
+                ),
+        )
+        .subcommand(
+            Command::new("spi")
+                .about("spi bus utilities")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("xfer")
+                        .arg(frequency_arg())
+                        .arg(
+                            Arg::new("mode")
+                                .long("mode")
+                                .short('m')
+                                .default_value("0")
+                                .value_parser(value_parser!(u8).range(0..4)),
+                        )
+                        .arg(
+                            Arg::new("data")
+                                .required(true)
+                                .action(ArgAction::Append)
+                                .help("hex bytes to clock out"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("i2c")
+                .about("i2c bus utilities")
+                .subcommand_required(true)
+                .subcommand(Command::new("scan").arg(frequency_arg())),
+        )
+}
+
+fn frequency_arg() -> Arg {
+    Arg::new("frequency")
+        .long("frequency")
+        .short('f')
+        .default_value("100000")
+        .value_parser(value_parser!(u32))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let matches = cli().get_matches();
+
+    match matches.subcommand() {
+        Some(("list", _)) => list().await,
+        Some(("eeprom", sub)) => eeprom(&matches, sub).await,
+        Some(("gpio", sub)) => gpio(&matches, sub).await,
+        Some(("uart", sub)) => uart(&matches, sub).await,
+        Some(("spi", sub)) => spi(&matches, sub).await,
+        Some(("i2c", sub)) => i2c(&matches, sub).await,
+        _ => unreachable!(),
+    }
+}
+
+async fn open(matches: &ArgMatches) -> Result<Interface> {
+    let port = PortLetter::try_from(*matches.get_one::<char>("port").unwrap())?;
+
+    match matches.get_one::<String>("serial") {
+        Some(sn) => Interface::with_serial_number(sn, port).await,
+        None => {
+            kftdi::list_devices()
+                .await?
+                .next()
+                .ok_or(Error::DeviceNotFound)?
+                .open_port(port)
+                .await
+        }
+    }
+}
+
+async fn list() -> Result<()> {
+    for dev in kftdi::list_devices().await? {
+        let ports: String = dev
+            .interfaces
+            .iter()
+            .map(|info| (b'A' + info.num) as char)
+            .collect();
+
+        println!(
+            "{:04x}:{:04x} {:?} serial={} product={} ports={}",
+            dev.dev.vendor_id(),
+            dev.dev.product_id(),
+            dev.device_type,
+            dev.dev.serial_number().unwrap_or("-"),
+            dev.dev.product_string().unwrap_or("-"),
+            ports
+        );
+    }
+
+    Ok(())
+}
+
+async fn eeprom(matches: &ArgMatches, sub: &ArgMatches) -> Result<()> {
+    let interface = open(matches).await?;
+
+    match sub.subcommand() {
+        Some(("dump", args)) => {
+            let words = interface.eeprom_read_all().await?;
+
+            match args.get_one::<String>("file") {
+                Some(file) => {
+                    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+                    std::fs::write(file, bytes)?;
+                }
+                None => {
+                    for (i, line) in words.chunks(8).enumerate() {
+                        let line: Vec<String> =
+                            line.iter().map(|word| format!("{:04x}", word)).collect();
+                        println!("{:04x}: {}", i * 8, line.join(" "));
+                    }
+                    println!("{:#?}", interface.read_eeprom().await?);
+                }
+            }
+        }
+        Some(("flash", args)) => {
+            let bytes = std::fs::read(args.get_one::<String>("file").unwrap())?;
+            let words: Vec<u16> = bytes
+                .chunks(2)
+                .map(|word| u16::from_le_bytes([word[0], *word.get(1).unwrap_or(&0)]))
+                .collect();
+            interface.eeprom_write_all(&words).await?;
+        }
+        _ => unreachable!(),
+    }
+
+    interface.close().await
+}
+
+async fn gpio(matches: &ArgMatches, sub: &ArgMatches) -> Result<()> {
+    let mpsse = open(matches).await?.into_mpsse().await?;
+
+    match sub.subcommand() {
+        Some(("get", _)) => println!("{:#06x}", mpsse.read_gpio().await?),
+        Some(("set", args)) => {
+            let pin = *args.get_one::<u8>("pin").unwrap();
+            let level = *args.get_one::<u8>("level").unwrap() != 0;
+
+            mpsse.set_pin(pin, level).await?;
+            mpsse.set_pin_direction(pin, Direction::Output).await?;
+
+            return mpsse.into_inner().close_keep_state().await;
+        }
+        _ => unreachable!(),
+    }
+
+    mpsse.into_inner().close().await
+}
+
+async fn uart(matches: &ArgMatches, sub: &ArgMatches) -> Result<()> {
+    let Some(("term", args)) = sub.subcommand() else {
+        unreachable!()
+    };
+
+    let interface = open(matches).await?;
+    interface
+        .set_baudrate(*args.get_one::<u32>("baud").unwrap())
+        .await?;
+    let uart = interface.clone().into_uart().await?;
+
+    let (mut rx, mut tx) = tokio::io::split(uart);
+    let mut stdin = tokio::io::stdin();
+    let mut stdout = tokio::io::stdout();
+
+    tokio::select! {
+        res = tokio::io::copy(&mut rx, &mut stdout) => res?,
+        res = tokio::io::copy(&mut stdin, &mut tx) => res?,
+    };
+    stdout.flush().await?;
+
+    interface.close().await
+}
+
+async fn spi(matches: &ArgMatches, sub: &ArgMatches) -> Result<()> {
+    let Some(("xfer", args)) = sub.subcommand() else {
+        unreachable!()
+    };
+
+    let mode = match args.get_one::<u8>("mode").unwrap() {
+        0 => SpiMode::Mode0,
+        1 => SpiMode::Mode1,
+        2 => SpiMode::Mode2,
+        _ => SpiMode::Mode3,
+    };
+    let mut buf = parse_hex(args.get_many::<String>("data").unwrap())?;
+
+    let interface = open(matches).await?;
+    let mut bus = SpiBus::new(
+        interface.clone().into_mpsse().await?,
+        *args.get_one::<u32>("frequency").unwrap(),
+    )
+    .await?;
+    bus.set_mode(mode).await?;
+    bus.set_cs(SPI_CS, true).await?;
+    let res = bus.transfer(&mut buf).await;
+    bus.set_cs(SPI_CS, false).await?;
+    res?;
+
+    println!("{}", format_hex(&buf));
+
+    interface.close().await
+}
+
+async fn i2c(matches: &ArgMatches, sub: &ArgMatches) -> Result<()> {
+    let Some(("scan", args)) = sub.subcommand() else {
+        unreachable!()
+    };
+
+    let interface = open(matches).await?;
+    let bus = I2cBus::new(
+        interface.clone().into_mpsse().await?,
+        *args.get_one::<u32>("frequency").unwrap(),
+    )
+    .await?;
+
+    for address in bus.scan().await? {
+        println!("{:#04x}", address);
+    }
+
+    interface.close().await
+}
+
+fn parse_hex<'a>(args: impl Iterator<Item = &'a String>) -> Result<Vec<u8>> {
+    let digits: String = args.map(|arg| arg.trim_start_matches("0x")).collect();
+
+    if digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidParameter(format!(
+            "invalid hex data {:?}",
+            digits
+        )));
+    }
+
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+fn format_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
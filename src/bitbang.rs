@@ -0,0 +1,458 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::time::Duration;
+use std::io::Write;
+use std::sync::Arc;
+
+use futures_core::Stream;
+
+use crate::{Bitmode, Error, Interface, Result};
+
+const BITBANG_CLOCK_MULTIPLIER: u32 = 16;
+const PWM_BUFFER_SAMPLES: usize = 16384;
+const MEASURE_CHUNK_SAMPLES: usize = 16384;
+
+#[derive(Clone, Debug)]
+pub struct AsyncBitbang {
+    interface: Interface,
+    direction: u8,
+    sample_rate: u32,
+    value: Arc<AtomicU8>,
+}
+
+impl AsyncBitbang {
+    pub async fn enter(interface: Interface, direction: u8, sample_rate: u32) -> Result<Self> {
+        if sample_rate < BITBANG_CLOCK_MULTIPLIER {
+            return Err(Error::InvalidParameter(format!(
+                "bitbang sample rate must be at least {} Hz, got {}",
+                BITBANG_CLOCK_MULTIPLIER, sample_rate
+            )));
+        }
+
+        interface.set_bitmode(0, Bitmode::Reset).await?;
+        interface.set_bitmode(direction, Bitmode::Bitbang).await?;
+        let baudrate = interface
+            .set_baudrate(sample_rate / BITBANG_CLOCK_MULTIPLIER)
+            .await?;
+        interface.purge_all().await?;
+
+        Ok(AsyncBitbang {
+            interface,
+            direction,
+            sample_rate: baudrate * BITBANG_CLOCK_MULTIPLIER,
+            value: Arc::default(),
+        })
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn direction(&self) -> u8 {
+        self.direction
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub async fn set_direction(&mut self, direction: u8) -> Result<()> {
+        self.interface
+            .set_bitmode(direction, Bitmode::Bitbang)
+            .await?;
+        self.direction = direction;
+
+        Ok(())
+    }
+
+    pub async fn write_pins(&self, values: &[u8]) -> Result<()> {
+        self.interface.write_all(values.to_vec()).await?;
+        if let Some(&last) = values.last() {
+            self.value.store(last, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    pub async fn read_pins(&self) -> Result<u8> {
+        self.interface.read_pins().await
+    }
+
+    pub fn read_pins_stream(&self) -> impl Stream<Item = Result<Vec<u8>>> {
+        let interface = self.interface.clone();
+        let max = interface.read_transfer_size();
+
+        futures_lite::stream::unfold(interface, move |interface| async move {
+            let res = interface.read_some(max).await;
+            Some((res, interface))
+        })
+    }
+
+    pub async fn exit(self) -> Result<Interface> {
+        self.interface.set_bitmode(0, Bitmode::Reset).await?;
+
+        Ok(self.interface)
+    }
+}
+
+pub async fn enter_async_bitbang(
+    interface: Interface,
+    direction: u8,
+    sample_rate: u32,
+) -> Result<AsyncBitbang> {
+    AsyncBitbang::enter(interface, direction, sample_rate).await
+}
+
+#[derive(Clone, Debug)]
+pub struct Pwm {
+    bitbang: AsyncBitbang,
+    pins: u8,
+    frequency: u32,
+    duty: f32,
+    pattern: Vec<u8>,
+}
+
+impl Pwm {
+    pub fn new(bitbang: AsyncBitbang, pins: u8, frequency: u32, duty: f32) -> Result<Self> {
+        if pins & !bitbang.direction != 0 {
+            return Err(Error::InvalidParameter(format!(
+                "pwm pins {:#04x} are not all outputs in direction {:#04x}",
+                pins, bitbang.direction
+            )));
+        }
+
+        let mut pwm = Pwm {
+            bitbang,
+            pins,
+            frequency,
+            duty,
+            pattern: Vec::new(),
+        };
+        pwm.rebuild()?;
+
+        Ok(pwm)
+    }
+
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    pub fn duty(&self) -> f32 {
+        self.duty
+    }
+
+    pub fn actual_frequency(&self) -> u32 {
+        self.bitbang.sample_rate / self.period_samples() as u32
+    }
+
+    pub fn set_frequency(&mut self, frequency: u32) -> Result<()> {
+        let previous = core::mem::replace(&mut self.frequency, frequency);
+        self.rebuild().inspect_err(|_| self.frequency = previous)
+    }
+
+    pub fn set_duty(&mut self, duty: f32) -> Result<()> {
+        let previous = core::mem::replace(&mut self.duty, duty);
+        self.rebuild().inspect_err(|_| self.duty = previous)
+    }
+
+    pub async fn run_for(&self, duration: Duration) -> Result<()> {
+        let mut remaining =
+            (duration.as_nanos() * self.bitbang.sample_rate as u128 / 1_000_000_000) as usize;
+
+        let others = self.other_pins();
+        let pattern = self.pattern_with(others);
+
+        while remaining > 0 {
+            let len = remaining.min(pattern.len());
+            self.bitbang.write_pins(&pattern[..len]).await?;
+            remaining -= len;
+        }
+
+        self.bitbang.write_pins(&[others]).await
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let pattern = self.pattern_with(self.other_pins());
+
+        loop {
+            self.bitbang.write_pins(&pattern).await?;
+        }
+    }
+
+    pub fn into_inner(self) -> AsyncBitbang {
+        self.bitbang
+    }
+
+    fn other_pins(&self) -> u8 {
+        self.bitbang.value.load(Ordering::Relaxed) & !self.pins
+    }
+
+    fn pattern_with(&self, others: u8) -> Vec<u8> {
+        self.pattern.iter().map(|&value| value | others).collect()
+    }
+
+    fn period_samples(&self) -> usize {
+        (self.bitbang.sample_rate / self.frequency.max(1)) as usize
+    }
+
+    fn rebuild(&mut self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.duty) {
+            return Err(Error::InvalidParameter(format!(
+                "pwm duty cycle must be in 0.0..=1.0, got {}",
+                self.duty
+            )));
+        }
+
+        let period = self.period_samples();
+        if self.frequency == 0 || period < 2 {
+            return Err(Error::InvalidParameter(format!(
+                "pwm frequency {} Hz is not achievable at a {} Hz sample rate",
+                self.frequency, self.bitbang.sample_rate
+            )));
+        }
+
+        let high = (period as f32 * self.duty).round() as usize;
+        let periods = (PWM_BUFFER_SAMPLES / period).max(1);
+        self.pattern = (0..period)
+            .map(|i| if i < high { self.pins } else { 0 })
+            .cycle()
+            .take(period * periods)
+            .collect();
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureChunk {
+    pub first_sample: u64,
+    pub sample_rate: u32,
+    pub samples: Vec<u8>,
+}
+
+impl CaptureChunk {
+    pub fn start_time(&self) -> core::time::Duration {
+        sample_time(self.first_sample, self.sample_rate)
+    }
+}
+
+fn sample_time(index: u64, sample_rate: u32) -> core::time::Duration {
+    core::time::Duration::from_nanos(index * 1_000_000_000 / sample_rate as u64)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrequencyMeasurement {
+    pub frequency: f64,
+    pub rising_edges: usize,
+    pub sample_rate: u32,
+    pub undersampled: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncBitbang {
+    interface: Interface,
+    direction: u8,
+    value: u8,
+    sample_rate: u32,
+}
+
+impl SyncBitbang {
+    pub async fn enter(interface: Interface, direction: u8, sample_rate: u32) -> Result<Self> {
+        if sample_rate < BITBANG_CLOCK_MULTIPLIER {
+            return Err(Error::InvalidParameter(format!(
+                "bitbang sample rate must be at least {} Hz, got {}",
+                BITBANG_CLOCK_MULTIPLIER, sample_rate
+            )));
+        }
+
+        interface.set_bitmode(0, Bitmode::Reset).await?;
+        interface.set_bitmode(direction, Bitmode::Syncbb).await?;
+        let baudrate = interface
+            .set_baudrate(sample_rate / BITBANG_CLOCK_MULTIPLIER)
+            .await?;
+        interface.purge_all().await?;
+
+        Ok(SyncBitbang {
+            interface,
+            direction,
+            value: 0,
+            sample_rate: baudrate * BITBANG_CLOCK_MULTIPLIER,
+        })
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_output(&mut self, value: u8) {
+        self.value = value;
+    }
+
+    pub async fn read_pins(&self) -> Result<u8> {
+        self.interface.read_pins().await
+    }
+
+    pub async fn transfer(&self, values: &[u8]) -> Result<Vec<u8>> {
+        let mut samples = vec![0u8; values.len()];
+        self.interface.write_all(values.to_vec()).await?;
+        self.interface.read_all(&mut samples).await?;
+
+        Ok(samples)
+    }
+
+    pub async fn measure_frequency(
+        &self,
+        pin: u8,
+        gate_time: Duration,
+    ) -> Result<FrequencyMeasurement> {
+        if pin > 7 {
+            return Err(Error::InvalidParameter(format!(
+                "bitbang pin must be in 0..=7, got {}",
+                pin
+            )));
+        }
+
+        let total = (gate_time.as_nanos() * self.sample_rate as u128 / 1_000_000_000) as usize;
+        let mask = 1 << pin;
+
+        let mut last = None;
+        let mut index = 0;
+        let mut rising = Vec::new();
+        let mut shortest_run = usize::MAX;
+        let mut run_start = 0;
+
+        while index < total {
+            let len = (total - index).min(MEASURE_CHUNK_SAMPLES);
+            let samples = self.transfer(&vec![self.value; len]).await?;
+
+            for &sample in &samples {
+                let level = sample & mask != 0;
+                if last.is_some_and(|last| last != level) {
+                    if level {
+                        rising.push(index);
+                    }
+                    if run_start > 0 {
+                        shortest_run = shortest_run.min(index - run_start);
+                    }
+                    run_start = index;
+                }
+                last = Some(level);
+                index += 1;
+            }
+        }
+
+        let frequency = match (rising.first(), rising.last()) {
+            (Some(first), Some(last)) if last > first => {
+                (rising.len() - 1) as f64 * self.sample_rate as f64 / (last - first) as f64
+            }
+            _ => 0.0,
+        };
+
+        let undersampled = shortest_run <= 2;
+        #[cfg(feature = "trace")]
+        if undersampled {
+            tracing::warn!(
+                frequency,
+                sample_rate = self.sample_rate,
+                "sample rate is too low for the measured signal"
+            );
+        }
+
+        Ok(FrequencyMeasurement {
+            frequency,
+            rising_edges: rising.len(),
+            sample_rate: self.sample_rate,
+            undersampled,
+        })
+    }
+
+    pub fn capture(&self, chunk_len: usize) -> impl Stream<Item = Result<CaptureChunk>> {
+        let bitbang = self.clone();
+
+        futures_lite::stream::unfold((bitbang, 0u64), move |(bitbang, first_sample)| async move {
+            let res = bitbang
+                .transfer(&vec![bitbang.value; chunk_len])
+                .await
+                .map(|samples| CaptureChunk {
+                    first_sample,
+                    sample_rate: bitbang.sample_rate,
+                    samples,
+                });
+
+            Some((res, (bitbang, first_sample + chunk_len as u64)))
+        })
+    }
+
+    pub async fn exit(self) -> Result<Interface> {
+        self.interface.set_bitmode(0, Bitmode::Reset).await?;
+
+        Ok(self.interface)
+    }
+}
+
+pub struct VcdWriter<W: Write> {
+    writer: W,
+    channels: usize,
+    mask: u8,
+    last: Option<u8>,
+}
+
+impl<W: Write> VcdWriter<W> {
+    pub fn new(mut writer: W, names: &[&str]) -> std::io::Result<Self> {
+        if names.len() > 8 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "at most 8 channels can be exported",
+            ));
+        }
+
+        writeln!(writer, "$timescale 1ns $end")?;
+        writeln!(writer, "$scope module ftdi $end")?;
+        for (i, name) in names.iter().enumerate() {
+            writeln!(writer, "$var wire 1 {} {} $end", vcd_id(i), name)?;
+        }
+        writeln!(writer, "$upscope $end")?;
+        writeln!(writer, "$enddefinitions $end")?;
+
+        Ok(VcdWriter {
+            writer,
+            channels: names.len(),
+            mask: ((1u16 << names.len()) - 1) as u8,
+            last: None,
+        })
+    }
+
+    pub fn write_chunk(&mut self, chunk: &CaptureChunk) -> std::io::Result<()> {
+        for (i, &sample) in chunk.samples.iter().enumerate() {
+            let changed = match self.last {
+                Some(last) => last ^ sample,
+                None => 0xff,
+            } & self.mask;
+
+            if changed == 0 {
+                continue;
+            }
+
+            let time = sample_time(chunk.first_sample + i as u64, chunk.sample_rate);
+            writeln!(self.writer, "#{}", time.as_nanos())?;
+            for bit in (0..self.channels).filter(|bit| changed & (1 << bit) != 0) {
+                writeln!(self.writer, "{}{}", (sample >> bit) & 1, vcd_id(bit))?;
+            }
+
+            self.last = Some(sample);
+        }
+
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn vcd_id(channel: usize) -> char {
+    (b'!' + channel as u8) as char
+}
@@ -0,0 +1,222 @@
+use core::time::Duration;
+use std::io;
+
+use futures_lite::future::block_on;
+use futures_lite::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+use crate::mpsse::{DataBits, MpsseCmdBuilder, MpsseInterface};
+use crate::{i2c, spi, Bitmode, DeviceInfo, FlowControl, InterfaceInfo, PortLetter, Result};
+
+pub fn list_devices() -> Result<Vec<DeviceInfo>> {
+    block_on(async { Ok(crate::list_devices().await?.collect()) })
+}
+
+#[derive(Clone, Debug)]
+pub struct Interface {
+    inner: crate::Interface,
+}
+
+impl Interface {
+    pub fn open(info: &mut InterfaceInfo) -> Result<Self> {
+        let inner = block_on(info.open())?;
+
+        Ok(Interface { inner })
+    }
+
+    pub fn with_serial_number(sn: &str, port: PortLetter) -> Result<Self> {
+        let inner = block_on(crate::Interface::with_serial_number(sn, port))?;
+
+        Ok(Interface { inner })
+    }
+
+    pub fn inner(&self) -> &crate::Interface {
+        &self.inner
+    }
+
+    pub fn set_baudrate(&self, baudrate: u32) -> Result<u32> {
+        block_on(self.inner.set_baudrate(baudrate))
+    }
+
+    pub fn set_flow_control(&self, flow_control: FlowControl) -> Result<()> {
+        block_on(self.inner.set_flow_control(flow_control))
+    }
+
+    pub fn set_latency_timer(&self, timer: Duration) -> Result<()> {
+        block_on(self.inner.set_latency_timer(timer))
+    }
+
+    pub fn set_bitmode(&self, bitmask: u8, bitmode: Bitmode) -> Result<()> {
+        block_on(self.inner.set_bitmode(bitmask, bitmode))
+    }
+
+    pub fn purge_all(&self) -> Result<()> {
+        block_on(self.inner.purge_all())
+    }
+
+    pub fn read_pins(&self) -> Result<u8> {
+        block_on(self.inner.read_pins())
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        block_on(self.inner.read(buf))
+    }
+
+    pub fn read_timeout(&self, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        block_on(self.inner.read_timeout(buf, timeout))
+    }
+
+    pub fn read_all(&self, buf: &mut [u8]) -> Result<()> {
+        block_on(self.inner.read_all(buf))
+    }
+
+    pub fn write_all(&self, buf: &[u8]) -> Result<()> {
+        block_on(self.inner.write_all(buf.to_vec()))
+    }
+
+    pub fn initialize_mpsse(&self) -> Result<()> {
+        block_on(self.inner.initialize_mpsse())
+    }
+
+    pub fn set_frequency(&self, frequency: u32) -> Result<u32> {
+        block_on(self.inner.set_frequency(frequency))
+    }
+
+    pub fn set_low_data_bits(&self, value: u8, direction: u8) -> Result<()> {
+        block_on(self.inner.set_low_data_bits(value, direction))
+    }
+
+    pub fn set_high_data_bits(&self, value: u8, direction: u8) -> Result<()> {
+        block_on(self.inner.set_high_data_bits(value, direction))
+    }
+
+    pub fn get_low_data_bits(&self) -> Result<DataBits> {
+        block_on(self.inner.get_low_data_bits())
+    }
+
+    pub fn get_high_data_bits(&self) -> Result<DataBits> {
+        block_on(self.inner.get_high_data_bits())
+    }
+
+    pub fn execute(&self, cmd: MpsseCmdBuilder) -> Result<Vec<u8>> {
+        block_on(self.inner.execute(cmd))
+    }
+
+    pub fn into_uart(self) -> Result<Uart> {
+        let inner = block_on(self.inner.into_uart())?;
+
+        Ok(Uart { inner })
+    }
+
+    pub fn close(self) -> Result<()> {
+        block_on(self.inner.close())
+    }
+}
+
+#[derive(Debug)]
+pub struct Uart {
+    inner: crate::Uart,
+}
+
+impl Uart {
+    pub fn inner(&self) -> &crate::Uart {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> Interface {
+        Interface {
+            inner: self.inner.into_inner(),
+        }
+    }
+}
+
+impl io::Read for Uart {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        block_on(self.inner.read(buf))
+    }
+}
+
+impl io::BufRead for Uart {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        block_on(self.inner.fill_buf())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        AsyncBufReadExt::consume(&mut self.inner, amt)
+    }
+}
+
+impl io::Write for Uart {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        block_on(self.inner.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        block_on(self.inner.flush())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpiBus {
+    inner: spi::SpiBus,
+}
+
+impl SpiBus {
+    pub fn new(interface: Interface, frequency: u32) -> Result<Self> {
+        let inner = block_on(async {
+            spi::SpiBus::new(interface.inner.into_mpsse().await?, frequency).await
+        })?;
+
+        Ok(SpiBus { inner })
+    }
+
+    pub fn set_frequency(&self, frequency: u32) -> Result<u32> {
+        block_on(self.inner.set_frequency(frequency))
+    }
+
+    pub fn set_cs(&mut self, mask: u8, active: bool) -> Result<()> {
+        block_on(self.inner.set_cs(mask, active))
+    }
+
+    pub fn transfer(&self, buf: &mut [u8]) -> Result<()> {
+        block_on(self.inner.transfer(buf))
+    }
+
+    pub fn write(&self, buf: &[u8]) -> Result<()> {
+        block_on(self.inner.write(buf))
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> Result<()> {
+        block_on(self.inner.read(buf))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct I2cBus {
+    inner: i2c::I2cBus,
+}
+
+impl I2cBus {
+    pub fn new(interface: Interface, frequency: u32) -> Result<Self> {
+        let inner = block_on(async {
+            i2c::I2cBus::new(interface.inner.into_mpsse().await?, frequency).await
+        })?;
+
+        Ok(I2cBus { inner })
+    }
+
+    pub fn set_speed(&self, frequency: u32) -> Result<u32> {
+        block_on(self.inner.set_speed(frequency))
+    }
+
+    pub fn write(&self, address: u8, buf: &[u8]) -> Result<()> {
+        block_on(self.inner.write(address, buf))
+    }
+
+    pub fn read(&self, address: u8, buf: &mut [u8]) -> Result<()> {
+        block_on(self.inner.read(address, buf))
+    }
+
+    pub fn write_read(&self, address: u8, write: &[u8], read: &mut [u8]) -> Result<()> {
+        block_on(self.inner.write_read(address, write, read))
+    }
+}
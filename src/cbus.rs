@@ -0,0 +1,76 @@
+use crate::{Bitmode, DeviceType, Error, Interface, Result};
+
+const FT232R_CBUS_IOMODE: u8 = 0x0a;
+const FTX_CBUS_IOMODE: u8 = 0x08;
+const FT232H_CBUS_IOMODE: u8 = 0x08;
+
+#[derive(Clone, Debug)]
+pub struct CbusGpio {
+    interface: Interface,
+    available: u8,
+}
+
+impl CbusGpio {
+    pub async fn new(interface: Interface) -> Result<Self> {
+        let (iomode, pins): (u8, &[usize]) = match interface.device_type {
+            DeviceType::FT232R => (FT232R_CBUS_IOMODE, &[0, 1, 2, 3]),
+            DeviceType::FtX => (FTX_CBUS_IOMODE, &[0, 1, 2, 3]),
+            DeviceType::FT232H => (FT232H_CBUS_IOMODE, &[5, 6, 8, 9]),
+            device_type => {
+                return Err(Error::InvalidParameter(format!(
+                    "cbus bitbang is not available on {:?}",
+                    device_type
+                )))
+            }
+        };
+
+        let eeprom = interface.read_eeprom().await?;
+        let available = pins
+            .iter()
+            .enumerate()
+            .filter(|&(_, &pin)| eeprom.cbus.get(pin) == Some(&iomode))
+            .fold(0, |mask, (bit, _)| mask | 1 << bit);
+
+        if available == 0 {
+            return Err(Error::InvalidParameter(
+                "no cbus pins are configured for i/o mode in the eeprom".into(),
+            ));
+        }
+
+        Ok(CbusGpio {
+            interface,
+            available,
+        })
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn available_pins(&self) -> u8 {
+        self.available
+    }
+
+    pub async fn set_cbus_pins(&self, mask: u8, value: u8) -> Result<()> {
+        if mask & !self.available != 0 {
+            return Err(Error::InvalidParameter(format!(
+                "cbus pins {:#03x} are not configured for i/o mode",
+                mask & !self.available
+            )));
+        }
+
+        self.interface
+            .set_bitmode((mask & 0x0f) << 4 | (value & mask & 0x0f), Bitmode::Cbus)
+            .await
+    }
+
+    pub async fn read_cbus_pins(&self) -> Result<u8> {
+        Ok(self.interface.read_pins().await? & 0x0f)
+    }
+
+    pub async fn exit(self) -> Result<Interface> {
+        self.interface.set_bitmode(0, Bitmode::Reset).await?;
+
+        Ok(self.interface)
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::packet::FtdiPacketDecoder;
+use crate::{Error, ModemStatus};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusFrame {
+    pub status: ModemStatus,
+    pub data: BytesMut,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineStatusAwareCodec {
+    decoder: FtdiPacketDecoder,
+    frames: VecDeque<StatusFrame>,
+}
+
+impl LineStatusAwareCodec {
+    pub fn new(max_packet_size: usize) -> Self {
+        LineStatusAwareCodec {
+            decoder: FtdiPacketDecoder::new(max_packet_size),
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub fn decode_transfer<'a>(
+        &self,
+        transfer: &'a [u8],
+    ) -> impl Iterator<Item = StatusFrame> + 'a {
+        self.decoder.packets(transfer).map(|packet| StatusFrame {
+            status: ModemStatus::from_bytes(packet.status),
+            data: BytesMut::from(packet.data),
+        })
+    }
+}
+
+// Packet boundaries are only meaningful within a single bulk transfer, so
+// every read from the underlying source must deliver exactly one transfer.
+impl Decoder for LineStatusAwareCodec {
+    type Item = StatusFrame;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<StatusFrame>, Error> {
+        if self.frames.is_empty() && !src.is_empty() {
+            let transfer = src.split();
+            let frames: Vec<_> = self.decode_transfer(&transfer).collect();
+            self.frames.extend(frames);
+        }
+
+        Ok(self.frames.pop_front())
+    }
+}
+
+impl Encoder<Bytes> for LineStatusAwareCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> Result<(), Error> {
+        dst.extend_from_slice(&item);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,409 @@
+use crate::{DeviceType, Error, Result};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelType {
+    Uart,
+    Fifo,
+    Opto,
+    Cpu,
+    Ft1284,
+}
+
+impl ChannelType {
+    fn from_bits(bits: u8) -> Self {
+        match bits {
+            1 => ChannelType::Fifo,
+            2 => ChannelType::Opto,
+            4 => ChannelType::Cpu,
+            8 => ChannelType::Ft1284,
+            _ => ChannelType::Uart,
+        }
+    }
+
+    fn bits(self) -> u8 {
+        match self {
+            ChannelType::Uart => 0,
+            ChannelType::Fifo => 1,
+            ChannelType::Opto => 2,
+            ChannelType::Cpu => 4,
+            ChannelType::Ft1284 => 8,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Driver {
+    D2xx,
+    Vcp,
+}
+
+impl Driver {
+    fn from_flag(vcp: bool) -> Self {
+        if vcp {
+            Driver::Vcp
+        } else {
+            Driver::D2xx
+        }
+    }
+
+    fn is_vcp(self) -> bool {
+        self == Driver::Vcp
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Channel {
+    pub kind: ChannelType,
+    pub driver: Driver,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DriveStrength {
+    pub current_ma: u8,
+    pub slow_slew: bool,
+    pub schmitt: bool,
+}
+
+impl DriveStrength {
+    fn from_bits(bits: u8) -> Self {
+        DriveStrength {
+            current_ma: 4 * ((bits & 0x03) + 1),
+            slow_slew: bits & 0x04 != 0,
+            schmitt: bits & 0x08 != 0,
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        ((self.current_ma / 4).clamp(1, 4) - 1)
+            | (self.slow_slew as u8) << 2
+            | (self.schmitt as u8) << 3
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Eeprom {
+    pub device_type: DeviceType,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub release: u16,
+    pub self_powered: bool,
+    pub remote_wakeup: bool,
+    pub max_power_ma: u16,
+    pub suspend_pull_downs: bool,
+    pub suspend_dbus7: bool,
+    pub use_serial: bool,
+    pub manufacturer: String,
+    pub product: String,
+    pub serial: String,
+    pub channels: Vec<Channel>,
+    pub drive: Vec<DriveStrength>,
+    pub cbus: Vec<u8>,
+    raw: Vec<u8>,
+}
+
+pub fn checksum(device_type: DeviceType, words: &[u16]) -> u16 {
+    let mut checksum: u16 = 0xaaaa;
+
+    for (addr, &word) in words[..words.len().saturating_sub(1)].iter().enumerate() {
+        if device_type == DeviceType::FtX && (0x12..0x40).contains(&addr) {
+            continue;
+        }
+
+        checksum ^= word;
+        checksum = checksum.rotate_left(1);
+    }
+
+    checksum
+}
+
+fn strings_start(device_type: DeviceType) -> usize {
+    match device_type {
+        DeviceType::FT232H | DeviceType::FtX => 0xa0,
+        DeviceType::FT232R => 0x18,
+        _ => 0x9a,
+    }
+}
+
+fn read_string(bytes: &[u8], ptr: usize) -> String {
+    let offset = bytes[ptr] as usize & (bytes.len() - 1);
+    let len = bytes[ptr + 1] as usize;
+
+    match bytes.get(offset..offset + len) {
+        Some(desc) if len >= 2 => {
+            let units: Vec<u16> = desc[2..]
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::new(),
+    }
+}
+
+fn unsupported(device_type: DeviceType) -> Error {
+    Error::InvalidParameter(format!("no eeprom layout for {:?}", device_type))
+}
+
+impl Eeprom {
+    pub fn parse(device_type: DeviceType, words: &[u16]) -> Result<Self> {
+        let size = device_type.eeprom_words();
+        if words.len() < size {
+            return Err(Error::InvalidParameter(format!(
+                "eeprom image has {} words, expected {}",
+                words.len(),
+                size
+            )));
+        }
+
+        let words = &words[..size];
+        let expected = checksum(device_type, words);
+        let actual = words[size - 1];
+        if expected != actual {
+            return Err(Error::EepromChecksum { expected, actual });
+        }
+
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+        let (channels, drive, cbus) = match device_type {
+            DeviceType::FT2232H => (
+                vec![
+                    Channel {
+                        kind: ChannelType::from_bits(bytes[0x00] & 0x07),
+                        driver: Driver::from_flag(bytes[0x00] & 0x08 != 0),
+                    },
+                    Channel {
+                        kind: ChannelType::from_bits(bytes[0x01] & 0x07),
+                        driver: Driver::from_flag(bytes[0x01] & 0x08 != 0),
+                    },
+                ],
+                vec![
+                    DriveStrength::from_bits(bytes[0x0c]),
+                    DriveStrength::from_bits(bytes[0x0c] >> 4),
+                    DriveStrength::from_bits(bytes[0x0d]),
+                    DriveStrength::from_bits(bytes[0x0d] >> 4),
+                ],
+                vec![],
+            ),
+            DeviceType::FT4232H => (
+                [(0x00, 0x08), (0x01, 0x08), (0x00, 0x80), (0x01, 0x80)]
+                    .iter()
+                    .map(|&(offset, mask)| Channel {
+                        kind: ChannelType::Uart,
+                        driver: Driver::from_flag(bytes[offset] & mask != 0),
+                    })
+                    .collect(),
+                vec![
+                    DriveStrength::from_bits(bytes[0x0c]),
+                    DriveStrength::from_bits(bytes[0x0c] >> 4),
+                    DriveStrength::from_bits(bytes[0x0d]),
+                    DriveStrength::from_bits(bytes[0x0d] >> 4),
+                ],
+                vec![],
+            ),
+            DeviceType::FT232H => (
+                vec![Channel {
+                    kind: ChannelType::from_bits(bytes[0x00] & 0x0f),
+                    driver: Driver::from_flag(bytes[0x00] & 0x10 != 0),
+                }],
+                vec![
+                    DriveStrength::from_bits(bytes[0x0c]),
+                    DriveStrength::from_bits(bytes[0x0d]),
+                ],
+                (0..10)
+                    .map(|i| (bytes[0x18 + i / 2] >> (4 * (i % 2))) & 0x0f)
+                    .collect(),
+            ),
+            DeviceType::FT232R => (
+                vec![Channel {
+                    kind: ChannelType::Uart,
+                    driver: Driver::from_flag(bytes[0x00] & 0x08 != 0),
+                }],
+                vec![],
+                (0..5)
+                    .map(|i| (bytes[0x14 + i / 2] >> (4 * (i % 2))) & 0x0f)
+                    .collect(),
+            ),
+            DeviceType::FtX => (
+                vec![Channel {
+                    kind: ChannelType::Uart,
+                    driver: Driver::from_flag(bytes[0x00] & 0x10 != 0),
+                }],
+                vec![
+                    DriveStrength::from_bits(bytes[0x0c]),
+                    DriveStrength::from_bits(bytes[0x0c] >> 4),
+                ],
+                bytes[0x1a..0x1e].to_vec(),
+            ),
+            _ => return Err(unsupported(device_type)),
+        };
+
+        Ok(Eeprom {
+            device_type,
+            vendor_id: u16::from_le_bytes([bytes[0x02], bytes[0x03]]),
+            product_id: u16::from_le_bytes([bytes[0x04], bytes[0x05]]),
+            release: u16::from_le_bytes([bytes[0x06], bytes[0x07]]),
+            self_powered: bytes[0x08] & 0x40 != 0,
+            remote_wakeup: bytes[0x08] & 0x20 != 0,
+            max_power_ma: bytes[0x09] as u16 * 2,
+            suspend_pull_downs: bytes[0x0a] & 0x04 != 0,
+            suspend_dbus7: matches!(device_type, DeviceType::FT2232H | DeviceType::FT232H)
+                && bytes[0x01] & 0x80 != 0,
+            use_serial: bytes[0x0a] & 0x08 != 0,
+            manufacturer: read_string(&bytes, 0x0e),
+            product: read_string(&bytes, 0x10),
+            serial: read_string(&bytes, 0x12),
+            channels,
+            drive,
+            cbus,
+            raw: bytes,
+        })
+    }
+
+    pub fn set_manufacturer(&mut self, manufacturer: &str) -> Result<()> {
+        self.set_string(|eeprom| &mut eeprom.manufacturer, manufacturer)
+    }
+
+    pub fn set_product(&mut self, product: &str) -> Result<()> {
+        self.set_string(|eeprom| &mut eeprom.product, product)
+    }
+
+    pub fn set_serial_number(&mut self, serial: &str) -> Result<()> {
+        self.set_string(|eeprom| &mut eeprom.serial, serial)?;
+        self.use_serial = true;
+
+        Ok(())
+    }
+
+    fn set_string(&mut self, field: fn(&mut Eeprom) -> &mut String, value: &str) -> Result<()> {
+        let old = core::mem::replace(field(self), value.to_string());
+
+        if let Err(err) = self.to_words() {
+            *field(self) = old;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    pub fn to_words(&self) -> Result<Vec<u16>> {
+        let size = self.device_type.eeprom_words() * 2;
+        let mut bytes = self.raw.clone();
+        bytes.resize(size, 0);
+
+        let channel = |i: usize| {
+            self.channels.get(i).copied().unwrap_or(Channel {
+                kind: ChannelType::Uart,
+                driver: Driver::Vcp,
+            })
+        };
+        let drive = |i: usize| self.drive.get(i).map_or(0, |drive| drive.bits());
+
+        match self.device_type {
+            DeviceType::FT2232H => {
+                for i in 0..2 {
+                    bytes[i] = (bytes[i] & !0x0f)
+                        | (channel(i).kind.bits() & 0x07)
+                        | (channel(i).driver.is_vcp() as u8) << 3;
+                }
+                bytes[0x01] = (bytes[0x01] & !0x80) | (self.suspend_dbus7 as u8) << 7;
+                bytes[0x0c] = drive(0) | drive(1) << 4;
+                bytes[0x0d] = drive(2) | drive(3) << 4;
+            }
+            DeviceType::FT4232H => {
+                for (i, &(offset, mask)) in [(0x00, 0x08), (0x01, 0x08), (0x00, 0x80), (0x01, 0x80)]
+                    .iter()
+                    .enumerate()
+                {
+                    if channel(i).driver.is_vcp() {
+                        bytes[offset] |= mask;
+                    } else {
+                        bytes[offset] &= !mask;
+                    }
+                }
+                bytes[0x0c] = drive(0) | drive(1) << 4;
+                bytes[0x0d] = drive(2) | drive(3) << 4;
+            }
+            DeviceType::FT232H => {
+                bytes[0x00] = (bytes[0x00] & !0x1f)
+                    | (channel(0).kind.bits() & 0x0f)
+                    | (channel(0).driver.is_vcp() as u8) << 4;
+                bytes[0x01] = (bytes[0x01] & !0x80) | (self.suspend_dbus7 as u8) << 7;
+                bytes[0x0c] = (bytes[0x0c] & 0xf0) | drive(0);
+                bytes[0x0d] = (bytes[0x0d] & 0xf0) | drive(1);
+                for (i, &function) in self.cbus.iter().take(10).enumerate() {
+                    let shift = 4 * (i % 2);
+                    bytes[0x18 + i / 2] =
+                        (bytes[0x18 + i / 2] & !(0x0f << shift)) | (function & 0x0f) << shift;
+                }
+            }
+            DeviceType::FT232R => {
+                bytes[0x00] = (bytes[0x00] & !0x08) | (channel(0).driver.is_vcp() as u8) << 3;
+                for (i, &function) in self.cbus.iter().take(5).enumerate() {
+                    let shift = 4 * (i % 2);
+                    bytes[0x14 + i / 2] =
+                        (bytes[0x14 + i / 2] & !(0x0f << shift)) | (function & 0x0f) << shift;
+                }
+            }
+            DeviceType::FtX => {
+                bytes[0x00] = (bytes[0x00] & !0x10) | (channel(0).driver.is_vcp() as u8) << 4;
+                bytes[0x0c] = drive(0) | drive(1) << 4;
+                for (i, &function) in self.cbus.iter().take(4).enumerate() {
+                    bytes[0x1a + i] = function;
+                }
+            }
+            _ => return Err(unsupported(self.device_type)),
+        }
+
+        bytes[0x02..0x04].copy_from_slice(&self.vendor_id.to_le_bytes());
+        bytes[0x04..0x06].copy_from_slice(&self.product_id.to_le_bytes());
+        bytes[0x06..0x08].copy_from_slice(&self.release.to_le_bytes());
+        bytes[0x08] = 0x80 | (self.self_powered as u8) << 6 | (self.remote_wakeup as u8) << 5;
+        bytes[0x09] = (self.max_power_ma.min(500) / 2) as u8;
+        bytes[0x0a] = (bytes[0x0a] & !0x0c)
+            | (self.suspend_pull_downs as u8) << 2
+            | (self.use_serial as u8) << 3;
+
+        let start = strings_start(self.device_type);
+        bytes[start..size - 2].fill(0);
+
+        let mut offset = start;
+        for (ptr, string) in [
+            (0x0e, &self.manufacturer),
+            (0x10, &self.product),
+            (0x12, &self.serial),
+        ] {
+            let units: Vec<u16> = string.encode_utf16().collect();
+            let len = 2 + units.len() * 2;
+
+            if offset + len > size - 2 || len > 0xff {
+                return Err(Error::InvalidParameter(
+                    "eeprom strings do not fit in the string area".into(),
+                ));
+            }
+
+            bytes[offset] = len as u8;
+            bytes[offset + 1] = 0x03;
+            for (i, unit) in units.iter().enumerate() {
+                bytes[offset + 2 + i * 2..offset + 4 + i * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+
+            bytes[ptr] = (offset as u8) | 0x80;
+            bytes[ptr + 1] = len as u8;
+            offset += len;
+        }
+
+        let mut words: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        let last = words.len() - 1;
+        words[last] = checksum(self.device_type, &words);
+
+        Ok(words)
+    }
+}
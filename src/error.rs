@@ -0,0 +1,119 @@
+use nusb::transfer::TransferError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("usb error: {0}")]
+    Usb(#[from] nusb::Error),
+    #[error("usb transfer failed: {0}")]
+    Transfer(TransferError),
+    #[error("control request {request:#04x} failed after {attempts} attempts: {source}")]
+    ControlFailed {
+        request: u8,
+        attempts: u32,
+        source: TransferError,
+    },
+    #[error("write failed after {written} bytes: {source}")]
+    WriteFailed {
+        written: usize,
+        source: TransferError,
+    },
+    #[error("failed to read active configuration: {0}")]
+    ActiveConfiguration(#[from] nusb::descriptors::ActiveConfigurationError),
+    #[error("device not found")]
+    DeviceNotFound,
+    #[error("unknown ftdi device version {version:#06x}")]
+    UnknownDevice { version: u16 },
+    #[error("invalid mpsse synchronization response {0:x?}")]
+    MpsseSync([u8; 2]),
+    #[error("mpsse engine rejected opcode {opcode:#04x}")]
+    BadMpsseCommand { opcode: u8 },
+    #[error("i2c address {address:#04x} not acknowledged")]
+    I2cNack { address: u8 },
+    #[error("smbus pec mismatch at {address:#04x}: expected {expected:#04x}, got {actual:#04x}")]
+    SmbusPec {
+        address: u8,
+        expected: u8,
+        actual: u8,
+    },
+    #[error("swd target kept responding WAIT")]
+    SwdWait,
+    #[error("swd target responded FAULT (CTRL/STAT {ctrl_stat:#010x})")]
+    SwdFault { ctrl_stat: u32 },
+    #[error("invalid swd acknowledge {ack:#05b}")]
+    SwdAck { ack: u8 },
+    #[error("swd read data parity error")]
+    SwdParity,
+    #[error("avr target did not enter programming mode")]
+    AvrIspSync,
+    #[error("unexpected response from device {0:x?}")]
+    InvalidResponse(Vec<u8>),
+    #[error("eeprom checksum mismatch: expected {expected:#06x}, found {actual:#06x}")]
+    EepromChecksum { expected: u16, actual: u16 },
+    #[error("eeprom verify failed at word {addr:#04x}: wrote {expected:#06x}, read {actual:#06x}")]
+    EepromVerify {
+        addr: u16,
+        expected: u16,
+        actual: u16,
+    },
+    #[error("flash verify failed at {address:#08x}: wrote {expected:#04x}, read {actual:#04x}")]
+    FlashVerify {
+        address: u32,
+        expected: u8,
+        actual: u8,
+    },
+    #[error("svf error at line {line}: {message}")]
+    Svf { line: usize, message: String },
+    #[error("svf tdo mismatch at line {line}: expected {expected:02x?}, read {actual:02x?}")]
+    SvfMismatch {
+        line: usize,
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
+    #[error("baudrate {requested} not achievable, closest is {actual}")]
+    BaudrateDeviation { requested: u32, actual: u32 },
+    #[error("operation requires {expected:?} mode, interface is in {actual:?} mode")]
+    WrongBitmode {
+        expected: crate::Bitmode,
+        actual: crate::Bitmode,
+    },
+    #[error("mpsse frequency {requested} not achievable, closest is {actual}")]
+    FrequencyDeviation { requested: u32, actual: u32 },
+    #[error("pin {pin} is already claimed by {owner}")]
+    PinConflict { pin: u8, owner: &'static str },
+    #[error("invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("device disconnected")]
+    Disconnected,
+    #[error("{operation} timed out after {completed} of {requested} bytes")]
+    Timeout {
+        operation: &'static str,
+        requested: usize,
+        completed: usize,
+    },
+}
+
+impl From<TransferError> for Error {
+    fn from(err: TransferError) -> Self {
+        match err {
+            TransferError::Disconnected => Error::Disconnected,
+            err => Error::Transfer(err),
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err {
+            Error::Disconnected => std::io::ErrorKind::NotConnected,
+            Error::Timeout { .. } => std::io::ErrorKind::TimedOut,
+            Error::InvalidParameter(_) => std::io::ErrorKind::InvalidInput,
+            _ => std::io::ErrorKind::Other,
+        };
+
+        std::io::Error::new(kind, err)
+    }
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,86 @@
+use core::time::Duration;
+
+use futures_core::Stream;
+use futures_lite::StreamExt;
+
+use crate::eeprom::ChannelType;
+use crate::{Bitmode, DeviceType, Error, FlowControl, Interface, Result};
+
+const FIFO_TRANSFER_PACKETS: usize = 32;
+const FIFO_QUEUE_DEPTH: usize = 16;
+const FIFO_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct SyncFifo {
+    interface: Interface,
+}
+
+impl SyncFifo {
+    pub async fn new(interface: Interface) -> Result<Self> {
+        match (interface.device_type, interface.num) {
+            (DeviceType::FT2232H | DeviceType::FT232H, 0) => {}
+            (device_type, num) => {
+                return Err(Error::InvalidParameter(format!(
+                    "synchronous fifo is not available on {:?} interface {}",
+                    device_type, num
+                )))
+            }
+        }
+
+        let eeprom = interface.read_eeprom().await?;
+        if eeprom.channels.first().map(|channel| channel.kind) != Some(ChannelType::Fifo) {
+            return Err(Error::InvalidParameter(
+                "eeprom must configure channel A as a 245 fifo".into(),
+            ));
+        }
+
+        interface.set_bitmode(0xff, Bitmode::Reset).await?;
+        interface
+            .set_latency_timer(Duration::from_millis(2))
+            .await?;
+        interface.set_flow_control(FlowControl::RtsCts).await?;
+        interface.set_read_transfer_packets(FIFO_TRANSFER_PACKETS)?;
+        interface.set_read_queue_depth(FIFO_QUEUE_DEPTH)?;
+        interface.set_bitmode(0xff, Bitmode::Syncff).await?;
+        interface.purge_all().await?;
+        interface.start_background_reader(FIFO_BUFFER_SIZE).await?;
+
+        Ok(SyncFifo { interface })
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn read_stream(&self) -> impl Stream<Item = Result<Vec<u8>>> {
+        let interface = self.interface.clone();
+        let max = interface.read_transfer_size() * FIFO_QUEUE_DEPTH;
+
+        futures_lite::stream::unfold(interface, move |interface| async move {
+            let res = interface.read_some(max).await;
+            Some((res, interface))
+        })
+    }
+
+    pub async fn write_stream<S>(&self, stream: S) -> Result<u64>
+    where
+        S: Stream<Item = Vec<u8>>,
+    {
+        let mut stream = core::pin::pin!(stream);
+        let mut written = 0;
+
+        while let Some(chunk) = stream.next().await {
+            written += chunk.len() as u64;
+            self.interface.write_all(chunk).await?;
+        }
+
+        Ok(written)
+    }
+
+    pub async fn close(self) -> Result<Interface> {
+        self.interface.stop_background_reader().await?;
+        self.interface.set_bitmode(0, Bitmode::Reset).await?;
+
+        Ok(self.interface)
+    }
+}
@@ -0,0 +1,276 @@
+use core::future::Future;
+use core::time::Duration;
+use std::sync::Arc;
+use std::time::Instant;
+
+use embedded_hal::digital::{ErrorKind, ErrorType, InputPin, OutputPin};
+use futures_core::Stream;
+
+use crate::mpsse::MpsseInterface;
+use crate::{Error, Interface, PinClaim, Result};
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bank {
+    Low,
+    High,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    Rising,
+    Falling,
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+    pub rising: bool,
+    pub timestamp: Instant,
+}
+
+#[derive(Clone, Debug)]
+pub struct Gpio {
+    interface: Interface,
+    claim: Arc<PinClaim>,
+}
+
+impl Gpio {
+    pub fn new(interface: Interface) -> Self {
+        let claim = Arc::new(interface.pins.claim("gpio"));
+
+        Gpio { interface, claim }
+    }
+
+    pub fn pin(&self, bank: Bank, index: u8) -> Result<Pin> {
+        if index > 7 {
+            return Err(Error::InvalidParameter(format!(
+                "gpio index must be 0..=7, got {}",
+                index
+            )));
+        }
+
+        self.claim.extend(1 << (bank as u16 * 8 + index as u16))?;
+
+        Ok(Pin {
+            interface: self.interface.clone(),
+            claim: self.claim.clone(),
+            bank,
+            mask: 1 << index,
+        })
+    }
+
+    pub fn adbus(&self, index: u8) -> Result<Pin> {
+        self.pin(Bank::Low, index)
+    }
+
+    pub fn acbus(&self, index: u8) -> Result<Pin> {
+        self.pin(Bank::High, index)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pin {
+    interface: Interface,
+    claim: Arc<PinClaim>,
+    bank: Bank,
+    mask: u8,
+}
+
+impl Pin {
+    pub async fn set_direction(&self, direction: Direction) -> Result<()> {
+        self.claim
+            .write(&self.interface, self.bank, |_, dir| match direction {
+                Direction::Output => *dir |= self.mask,
+                Direction::Input => *dir &= !self.mask,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_level(&self, high: bool) -> Result<()> {
+        self.claim
+            .write(&self.interface, self.bank, |value, _| {
+                if high {
+                    *value |= self.mask;
+                } else {
+                    *value &= !self.mask;
+                }
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn read_level(&self) -> Result<bool> {
+        let bits = match self.bank {
+            Bank::Low => self.interface.get_low_data_bits().await?,
+            Bank::High => self.interface.get_high_data_bits().await?,
+        };
+
+        Ok(bits.0 & self.mask != 0)
+    }
+
+    pub fn subscribe_edges(
+        &self,
+        kind: EdgeKind,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<Edge>> {
+        futures_lite::stream::unfold(
+            (self.clone(), None, Instant::now(), false),
+            move |(pin, mut level, mut since, done)| async move {
+                if done {
+                    return None;
+                }
+
+                loop {
+                    let poller = &pin.interface.edge_poller;
+                    let (bits, timestamp) =
+                        match poller.next(&pin.interface, since, poll_interval).await {
+                            Ok(sample) => sample,
+                            Err(err) => return Some((Err(err), (pin, level, since, true))),
+                        };
+                    since = timestamp;
+
+                    let high = (bits >> (pin.bank as u16 * 8)) & pin.mask as u16 != 0;
+                    let previous = level.replace(high);
+
+                    let matches = match kind {
+                        EdgeKind::Rising => high,
+                        EdgeKind::Falling => !high,
+                        EdgeKind::Both => true,
+                    };
+                    if previous.is_some_and(|previous| previous != high) && matches {
+                        let edge = Edge {
+                            rising: high,
+                            timestamp,
+                        };
+                        return Some((Ok(edge), (pin, level, since, false)));
+                    }
+                }
+            },
+        )
+    }
+
+    async fn wait_for_level(&self, high: bool) -> Result<()> {
+        while self.read_level().await? != high {
+            futures_timer::Delay::new(WAIT_POLL_INTERVAL).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct EdgePoller {
+    sample: async_lock::Mutex<Option<(u16, Instant)>>,
+}
+
+impl EdgePoller {
+    async fn next(
+        &self,
+        interface: &Interface,
+        since: Instant,
+        poll_interval: Duration,
+    ) -> Result<(u16, Instant)> {
+        loop {
+            let mut sample = self.sample.lock().await;
+            let wait = match *sample {
+                Some((bits, at)) if at > since => return Ok((bits, at)),
+                Some((_, at)) => poll_interval.saturating_sub(at.elapsed()),
+                None => Duration::ZERO,
+            };
+
+            if wait.is_zero() {
+                let bits = interface.read_gpio().await?;
+                let latest = (bits, Instant::now());
+                *sample = Some(latest);
+                return Ok(latest);
+            }
+
+            drop(sample);
+            futures_timer::Delay::new(wait).await;
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn block_on<T>(fut: impl Future<Output = Result<T>>) -> Result<T> {
+    use tokio::runtime::{Handle, RuntimeFlavor};
+
+    match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+        Ok(RuntimeFlavor::CurrentThread) => Err(Error::InvalidParameter(
+            "blocking gpio calls are not supported on a current_thread runtime".into(),
+        )),
+        Ok(_) => tokio::task::block_in_place(|| futures_lite::future::block_on(fut)),
+        Err(_) => futures_lite::future::block_on(fut),
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+fn block_on<T>(fut: impl Future<Output = Result<T>>) -> Result<T> {
+    futures_lite::future::block_on(fut)
+}
+
+impl embedded_hal::digital::Error for Error {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl ErrorType for Pin {
+    type Error = Error;
+}
+
+impl OutputPin for Pin {
+    fn set_low(&mut self) -> Result<()> {
+        block_on(self.set_level(false))
+    }
+
+    fn set_high(&mut self) -> Result<()> {
+        block_on(self.set_level(true))
+    }
+}
+
+impl InputPin for Pin {
+    fn is_high(&mut self) -> Result<bool> {
+        block_on(self.read_level())
+    }
+
+    fn is_low(&mut self) -> Result<bool> {
+        block_on(self.read_level()).map(|high| !high)
+    }
+}
+
+impl embedded_hal_async::digital::Wait for Pin {
+    async fn wait_for_high(&mut self) -> Result<()> {
+        self.wait_for_level(true).await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<()> {
+        self.wait_for_level(false).await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<()> {
+        self.wait_for_level(false).await?;
+        self.wait_for_level(true).await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<()> {
+        self.wait_for_level(true).await?;
+        self.wait_for_level(false).await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<()> {
+        let level = self.read_level().await?;
+        self.wait_for_level(!level).await
+    }
+}
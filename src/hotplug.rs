@@ -0,0 +1,67 @@
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::collections::HashSet;
+
+use futures_core::Stream;
+use nusb::hotplug::{HotplugEvent, HotplugWatch};
+use nusb::DeviceId;
+
+use crate::{device_info, DeviceFilter, DeviceInfo, Result};
+
+#[derive(Clone, Debug)]
+pub enum DeviceEvent {
+    Arrived(DeviceInfo),
+    Left(DeviceId),
+}
+
+pub struct DeviceWatcher {
+    watch: HotplugWatch,
+    filter: DeviceFilter,
+    known: HashSet<DeviceId>,
+}
+
+pub async fn watch_devices() -> Result<DeviceWatcher> {
+    watch_devices_with_filter(DeviceFilter::default()).await
+}
+
+pub async fn watch_devices_with_filter(filter: DeviceFilter) -> Result<DeviceWatcher> {
+    let watch = nusb::watch_devices()?;
+    let known = nusb::list_devices()
+        .await?
+        .filter(|dev| filter.matches(dev))
+        .map(|dev| dev.id())
+        .collect();
+
+    Ok(DeviceWatcher {
+        watch,
+        filter,
+        known,
+    })
+}
+
+impl Stream for DeviceWatcher {
+    type Item = DeviceEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let event = match Pin::new(&mut this.watch).poll_next(cx) {
+                Poll::Ready(Some(event)) => event,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match event {
+                HotplugEvent::Connected(dev) if this.filter.matches(&dev) => {
+                    this.known.insert(dev.id());
+                    return Poll::Ready(Some(DeviceEvent::Arrived(device_info(dev))));
+                }
+                HotplugEvent::Disconnected(id) if this.known.remove(&id) => {
+                    return Poll::Ready(Some(DeviceEvent::Left(id)));
+                }
+                _ => {}
+            }
+        }
+    }
+}
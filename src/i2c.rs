@@ -0,0 +1,231 @@
+pub mod eeprom24;
+pub mod smbus;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use kftdi_core::i2c::{
+    push_read_byte, push_start, push_stop, push_write_byte, PinBase, SCL, SDA_IN, SDA_OUT,
+};
+
+use crate::gpio::Bank;
+use crate::mpsse::{Exclusive, Mpsse, MpsseInterface, SendImmediate, RTCK};
+use crate::pins::PinBank;
+use crate::{DeviceType, Error, Interface, PinClaim, Result};
+
+const SCAN_FIRST: u8 = 0x08;
+const SCAN_LAST: u8 = 0x77;
+
+#[derive(Clone, Debug)]
+pub struct I2cBus {
+    interface: Interface,
+    pins: Arc<PinClaim>,
+    stretch_timeout: Option<Duration>,
+    release_high_lines: bool,
+}
+
+impl I2cBus {
+    pub async fn new(mpsse: Mpsse, frequency: u32) -> Result<Self> {
+        let interface = mpsse.into_inner();
+        let pins = Arc::new(interface.claim_pins("i2c", (SCL | SDA_OUT | SDA_IN) as u16)?);
+        let drive_only_zero = interface.device_type == DeviceType::FT232H;
+        match interface.device_type {
+            DeviceType::FT2232C => interface.disable_3phase_clocking().await?,
+            _ => interface.enable_3phase_clocking().await?,
+        }
+        if drive_only_zero {
+            interface
+                .set_open_drain(SCL | SDA_OUT | SDA_IN, 0x00)
+                .await?;
+        }
+        interface.set_frequency(frequency).await?;
+
+        let idle = match drive_only_zero {
+            true => SCL | SDA_OUT,
+            false => 0,
+        };
+        pins.write(&interface, Bank::Low, |value, direction| {
+            *value = idle;
+            *direction = idle;
+        })
+        .await?;
+
+        Ok(I2cBus {
+            interface,
+            pins,
+            stretch_timeout: None,
+            release_high_lines: !drive_only_zero,
+        })
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub async fn set_speed(&self, frequency: u32) -> Result<u32> {
+        self.interface.set_frequency(frequency).await
+    }
+
+    pub fn clock_stretching(&self) -> Option<Duration> {
+        self.stretch_timeout
+    }
+
+    pub async fn set_clock_stretching(&mut self, timeout: Option<Duration>) -> Result<()> {
+        // Without drive-only-zero SCL is driven high, so a slave holding it
+        // low would be fighting the FTDI.
+        if timeout.is_some() && self.release_high_lines {
+            return Err(Error::InvalidParameter(format!(
+                "i2c clock stretching requires drive-only-zero outputs, which {:?} lacks",
+                self.interface.device_type
+            )));
+        }
+
+        match timeout {
+            Some(_) => {
+                self.pins.extend(RTCK as u16)?;
+                self.pins
+                    .write(&self.interface, Bank::Low, |_, direction| {
+                        *direction &= !RTCK;
+                    })
+                    .await?;
+                self.interface.enable_adaptive_clocking().await?
+            }
+            None => self.interface.disable_adaptive_clocking().await?,
+        }
+        self.stretch_timeout = timeout;
+
+        Ok(())
+    }
+
+    pub async fn write(&self, address: u8, buf: &[u8]) -> Result<()> {
+        self.transaction(address, buf, &mut []).await
+    }
+
+    pub async fn read(&self, address: u8, buf: &mut [u8]) -> Result<()> {
+        self.transaction(address, &[], buf).await
+    }
+
+    pub async fn write_read(&self, address: u8, write: &[u8], read: &mut [u8]) -> Result<()> {
+        self.transaction(address, write, read).await
+    }
+
+    pub async fn scan(&self) -> Result<Vec<u8>> {
+        let addresses = SCAN_FIRST..=SCAN_LAST;
+        let (_pins, base) = self.lock_pins().await;
+
+        let mut cmd = Vec::new();
+        for address in addresses.clone() {
+            push_start(&mut cmd, base);
+            push_write_byte(&mut cmd, base, address << 1);
+            push_stop(&mut cmd, base);
+        }
+        cmd.push(SendImmediate::byte());
+
+        let mut acks = vec![0u8; addresses.len()];
+        let exclusive = self.interface.exclusive().await;
+        self.run(&exclusive, cmd, &mut acks).await?;
+
+        Ok(addresses
+            .zip(acks)
+            .filter(|(_, ack)| ack & 1 == 0)
+            .map(|(address, _)| address)
+            .collect())
+    }
+
+    async fn transaction(&self, address: u8, write: &[u8], read: &mut [u8]) -> Result<()> {
+        check_address(address)?;
+        let (_pins, base) = self.lock_pins().await;
+
+        let mut cmd = Vec::new();
+        let mut write_acks = 0;
+
+        if !write.is_empty() || read.is_empty() {
+            push_start(&mut cmd, base);
+            push_write_byte(&mut cmd, base, address << 1);
+            for &byte in write {
+                push_write_byte(&mut cmd, base, byte);
+            }
+            write_acks = write.len() + 1;
+        }
+
+        let read_acks = if read.is_empty() {
+            0
+        } else {
+            push_start(&mut cmd, base);
+            push_write_byte(&mut cmd, base, (address << 1) | 1);
+            for i in 0..read.len() {
+                push_read_byte(&mut cmd, base, i + 1 < read.len());
+            }
+            1
+        };
+
+        push_stop(&mut cmd, base);
+        cmd.push(SendImmediate::byte());
+
+        let mut response = vec![0u8; write_acks + read_acks + read.len()];
+        let exclusive = self.interface.exclusive().await;
+        self.run(&exclusive, cmd, &mut response).await?;
+
+        let (acks, data) = response.split_at(write_acks + read_acks);
+        if acks.iter().any(|ack| ack & 1 != 0) {
+            return Err(Error::I2cNack { address });
+        }
+
+        read.copy_from_slice(data);
+
+        Ok(())
+    }
+
+    async fn lock_pins(&self) -> (PinBank<'_>, PinBase) {
+        let pins = self.pins.bank(Bank::Low).await;
+        let (value, direction) = pins.others();
+
+        (
+            pins,
+            PinBase {
+                value,
+                direction,
+                release_high_lines: self.release_high_lines,
+            },
+        )
+    }
+
+    async fn run(&self, exclusive: &Exclusive<'_>, cmd: Vec<u8>, buf: &mut [u8]) -> Result<()> {
+        exclusive.write(cmd).await?;
+
+        let res = self.read_response(exclusive, buf).await;
+        if let Err(Error::Timeout { .. }) = res {
+            exclusive.purge_rx().await?;
+        }
+
+        res
+    }
+
+    async fn read_response(&self, exclusive: &Exclusive<'_>, buf: &mut [u8]) -> Result<()> {
+        let Some(timeout) = self.stretch_timeout else {
+            return exclusive.read(buf).await;
+        };
+
+        let completed = exclusive.read_exact_timeout(buf, timeout).await?;
+        if completed < buf.len() {
+            return Err(Error::Timeout {
+                operation: "i2c clock stretch",
+                requested: buf.len(),
+                completed,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn check_address(address: u8) -> Result<()> {
+    if address > 0x7f {
+        return Err(Error::InvalidParameter(format!(
+            "i2c address {:#04x} is not a 7-bit address",
+            address
+        )));
+    }
+
+    Ok(())
+}
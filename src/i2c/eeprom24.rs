@@ -0,0 +1,151 @@
+use std::time::{Duration, Instant};
+
+use super::I2cBus;
+use crate::{Error, Result};
+
+const WRITE_CYCLE_TIMEOUT: Duration = Duration::from_millis(20);
+const ACK_POLL_INTERVAL: Duration = Duration::from_micros(500);
+const ONE_BYTE_BANK: usize = 0x100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressWidth {
+    One,
+    Two,
+}
+
+#[derive(Clone, Debug)]
+pub struct Eeprom24 {
+    bus: I2cBus,
+    address: u8,
+    width: AddressWidth,
+    page_size: usize,
+    size: usize,
+}
+
+impl Eeprom24 {
+    pub fn new(
+        bus: I2cBus,
+        address: u8,
+        width: AddressWidth,
+        page_size: usize,
+        size: usize,
+    ) -> Result<Self> {
+        let max_size = match width {
+            AddressWidth::One => ONE_BYTE_BANK << 3,
+            AddressWidth::Two => 0x10000,
+        };
+        if page_size == 0 || !page_size.is_power_of_two() || size > max_size {
+            return Err(Error::InvalidParameter(format!(
+                "unsupported eeprom geometry: {} bytes with {} byte pages",
+                size, page_size
+            )));
+        }
+
+        Ok(Eeprom24 {
+            bus,
+            address,
+            width,
+            page_size,
+            size,
+        })
+    }
+
+    pub fn bus(&self) -> &I2cBus {
+        &self.bus
+    }
+
+    pub fn into_inner(self) -> I2cBus {
+        self.bus
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub async fn read(&self, offset: usize, buf: &mut [u8]) -> Result<()> {
+        self.check_range(offset, buf.len())?;
+
+        let bank = match self.width {
+            AddressWidth::One => ONE_BYTE_BANK,
+            AddressWidth::Two => self.size.max(1),
+        };
+
+        let mut done = 0;
+        while done < buf.len() {
+            let start = offset + done;
+            let len = (bank - start % bank).min(buf.len() - done);
+            let (address, header) = self.header(start);
+
+            self.bus
+                .write_read(address, &header, &mut buf[done..done + len])
+                .await?;
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    pub async fn write(&self, offset: usize, data: &[u8]) -> Result<()> {
+        self.check_range(offset, data.len())?;
+
+        let mut done = 0;
+        while done < data.len() {
+            let start = offset + done;
+            let len = (self.page_size - start % self.page_size).min(data.len() - done);
+            let (address, mut cmd) = self.header(start);
+            cmd.extend_from_slice(&data[done..done + len]);
+
+            self.bus.write(address, &cmd).await?;
+            self.wait_write_cycle(address).await?;
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    async fn wait_write_cycle(&self, address: u8) -> Result<()> {
+        let deadline = Instant::now() + WRITE_CYCLE_TIMEOUT;
+
+        loop {
+            match self.bus.write(address, &[]).await {
+                Ok(()) => return Ok(()),
+                Err(Error::I2cNack { .. }) if Instant::now() < deadline => {
+                    futures_timer::Delay::new(ACK_POLL_INTERVAL).await;
+                }
+                Err(Error::I2cNack { .. }) => {
+                    return Err(Error::Timeout {
+                        operation: "eeprom write cycle",
+                        requested: 0,
+                        completed: 0,
+                    })
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn header(&self, offset: usize) -> (u8, Vec<u8>) {
+        match self.width {
+            AddressWidth::One => (
+                self.address | ((offset >> 8) as u8 & 0x07),
+                vec![offset as u8],
+            ),
+            AddressWidth::Two => (self.address, (offset as u16).to_be_bytes().to_vec()),
+        }
+    }
+
+    fn check_range(&self, offset: usize, len: usize) -> Result<()> {
+        if offset + len > self.size {
+            return Err(Error::InvalidParameter(format!(
+                "range {:#x}+{:#x} exceeds eeprom size {:#x}",
+                offset, len, self.size
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use super::{check_address, push_read_byte, push_start, push_stop, push_write_byte, I2cBus};
+use crate::mpsse::SendImmediate;
+use crate::{Error, Result};
+
+const BLOCK_MAX: usize = 32;
+
+#[derive(Clone, Debug)]
+pub struct SmBus {
+    bus: I2cBus,
+    pec: bool,
+}
+
+impl SmBus {
+    pub fn new(bus: I2cBus) -> Self {
+        SmBus { bus, pec: false }
+    }
+
+    pub fn bus(&self) -> &I2cBus {
+        &self.bus
+    }
+
+    pub fn into_inner(self) -> I2cBus {
+        self.bus
+    }
+
+    pub fn pec(&self) -> bool {
+        self.pec
+    }
+
+    pub fn set_pec(&mut self, pec: bool) {
+        self.pec = pec;
+    }
+
+    pub async fn write_byte(&self, address: u8, value: u8) -> Result<()> {
+        self.write(address, &[value]).await
+    }
+
+    pub async fn read_byte(&self, address: u8) -> Result<u8> {
+        let mut buf = [0u8];
+        self.write_read(address, &[], &mut buf).await?;
+
+        Ok(buf[0])
+    }
+
+    pub async fn write_byte_data(&self, address: u8, command: u8, value: u8) -> Result<()> {
+        self.write(address, &[command, value]).await
+    }
+
+    pub async fn read_byte_data(&self, address: u8, command: u8) -> Result<u8> {
+        let mut buf = [0u8];
+        self.write_read(address, &[command], &mut buf).await?;
+
+        Ok(buf[0])
+    }
+
+    pub async fn write_word_data(&self, address: u8, command: u8, value: u16) -> Result<()> {
+        let [lo, hi] = value.to_le_bytes();
+
+        self.write(address, &[command, lo, hi]).await
+    }
+
+    pub async fn read_word_data(&self, address: u8, command: u8) -> Result<u16> {
+        let mut buf = [0u8; 2];
+        self.write_read(address, &[command], &mut buf).await?;
+
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    pub async fn block_write(&self, address: u8, command: u8, data: &[u8]) -> Result<()> {
+        check_block_len(data.len())?;
+
+        let mut msg = vec![command, data.len() as u8];
+        msg.extend_from_slice(data);
+
+        self.write(address, &msg).await
+    }
+
+    pub async fn block_read(&self, address: u8, command: u8) -> Result<Vec<u8>> {
+        check_address(address)?;
+        let (_pins, base) = self.bus.lock_pins().await;
+        let exclusive = self.bus.interface.exclusive().await;
+
+        let mut cmd = Vec::new();
+        push_start(&mut cmd, base);
+        push_write_byte(&mut cmd, base, address << 1);
+        push_write_byte(&mut cmd, base, command);
+        push_start(&mut cmd, base);
+        push_write_byte(&mut cmd, base, (address << 1) | 1);
+        push_read_byte(&mut cmd, base, true);
+        cmd.push(SendImmediate::byte());
+
+        let mut head = [0u8; 4];
+        self.bus.run(&exclusive, cmd, &mut head).await?;
+
+        let count = head[3] as usize;
+        let remaining = count + self.pec as usize;
+        if head[..3].iter().any(|ack| ack & 1 != 0) || remaining == 0 || count > BLOCK_MAX {
+            let mut cmd = Vec::new();
+            push_read_byte(&mut cmd, base, false);
+            push_stop(&mut cmd, base);
+            cmd.push(SendImmediate::byte());
+
+            let mut discard = [0u8];
+            self.bus.run(&exclusive, cmd, &mut discard).await?;
+
+            if head[..3].iter().any(|ack| ack & 1 != 0) {
+                return Err(Error::I2cNack { address });
+            }
+            if count > BLOCK_MAX {
+                return Err(Error::InvalidResponse(head.to_vec()));
+            }
+
+            return Ok(Vec::new());
+        }
+
+        let mut cmd = Vec::new();
+        for i in 0..remaining {
+            push_read_byte(&mut cmd, base, i + 1 < remaining);
+        }
+        push_stop(&mut cmd, base);
+        cmd.push(SendImmediate::byte());
+
+        let mut data = vec![0u8; remaining];
+        self.bus.run(&exclusive, cmd, &mut data).await?;
+
+        if self.pec {
+            let actual = data.pop().unwrap_or_default();
+            let expected = crc8(
+                &[
+                    &[address << 1, command, (address << 1) | 1, head[3]][..],
+                    &data,
+                ]
+                .concat(),
+            );
+            check_pec(address, expected, actual)?;
+        }
+
+        Ok(data)
+    }
+
+    async fn write(&self, address: u8, msg: &[u8]) -> Result<()> {
+        if !self.pec {
+            return self.bus.write(address, msg).await;
+        }
+
+        let mut msg = msg.to_vec();
+        msg.push(crc8(&[&[address << 1][..], &msg].concat()));
+
+        self.bus.write(address, &msg).await
+    }
+
+    async fn write_read(&self, address: u8, write: &[u8], read: &mut [u8]) -> Result<()> {
+        if !self.pec {
+            return self.bus.write_read(address, write, read).await;
+        }
+
+        let mut buf = vec![0u8; read.len() + 1];
+        self.bus.write_read(address, write, &mut buf).await?;
+
+        let actual = buf.pop().unwrap_or_default();
+        let mut covered = Vec::new();
+        if !write.is_empty() {
+            covered.push(address << 1);
+            covered.extend_from_slice(write);
+        }
+        covered.push((address << 1) | 1);
+        covered.extend_from_slice(&buf);
+        check_pec(address, crc8(&covered), actual)?;
+
+        read.copy_from_slice(&buf);
+
+        Ok(())
+    }
+}
+
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+fn check_pec(address: u8, expected: u8, actual: u8) -> Result<()> {
+    if expected != actual {
+        return Err(Error::SmbusPec {
+            address,
+            expected,
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+fn check_block_len(len: usize) -> Result<()> {
+    if len > BLOCK_MAX {
+        return Err(Error::InvalidParameter(format!(
+            "smbus block of {} bytes exceeds the {} byte limit",
+            len, BLOCK_MAX
+        )));
+    }
+
+    Ok(())
+}
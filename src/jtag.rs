@@ -0,0 +1,265 @@
+pub mod svf;
+
+pub use kftdi_core::jtag::TapState;
+
+use crate::gpio::Bank;
+use crate::mpsse::{BitOrder, ClockEdge, MpsseCmdBuilder, MpsseInterface, RTCK};
+use crate::{Error, Interface, PinClaim, Result};
+
+const TCK: u8 = 1 << 0;
+const TDI: u8 = 1 << 1;
+const TDO: u8 = 1 << 2;
+const TMS: u8 = 1 << 3;
+
+const MAX_CHAIN_DEVICES: usize = 32;
+const MAX_IR_BITS: usize = 1024;
+const IDCODE_BITS: usize = 32;
+
+const JEP106: &[(u8, u8, &str)] = &[
+    (0, 0x01, "AMD"),
+    (0, 0x04, "Fujitsu"),
+    (0, 0x09, "Intel"),
+    (0, 0x0e, "Freescale"),
+    (0, 0x15, "NXP"),
+    (0, 0x17, "Texas Instruments"),
+    (0, 0x1f, "Atmel"),
+    (0, 0x20, "STMicroelectronics"),
+    (0, 0x21, "Lattice"),
+    (0, 0x29, "Microchip"),
+    (0, 0x34, "Cypress"),
+    (0, 0x41, "Infineon"),
+    (0, 0x49, "Xilinx"),
+    (0, 0x65, "Analog Devices"),
+    (0, 0x6e, "Altera"),
+    (4, 0x3b, "ARM"),
+    (8, 0x0d, "Gowin"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TapInfo {
+    pub idcode: Option<u32>,
+}
+
+impl TapInfo {
+    pub fn is_bypass(&self) -> bool {
+        self.idcode.is_none()
+    }
+
+    pub fn version(&self) -> Option<u8> {
+        self.idcode.map(|id| (id >> 28) as u8)
+    }
+
+    pub fn part(&self) -> Option<u16> {
+        self.idcode.map(|id| (id >> 12) as u16)
+    }
+
+    pub fn manufacturer_id(&self) -> Option<(u8, u8)> {
+        self.idcode
+            .map(|id| (((id >> 8) & 0x0f) as u8, ((id >> 1) & 0x7f) as u8))
+    }
+
+    pub fn manufacturer(&self) -> Option<&'static str> {
+        let (bank, code) = self.manufacturer_id()?;
+
+        JEP106
+            .iter()
+            .find(|&&(b, c, _)| b == bank && c == code)
+            .map(|&(_, _, name)| name)
+    }
+}
+
+#[derive(Debug)]
+pub struct Jtag {
+    interface: Interface,
+    pins: PinClaim,
+    state: TapState,
+}
+
+impl Jtag {
+    pub async fn new(interface: Interface, frequency: u32) -> Result<Self> {
+        let pins = interface.claim_pins("jtag", (TCK | TDI | TDO | TMS) as u16)?;
+        interface.initialize_mpsse().await?;
+        interface.set_frequency(frequency).await?;
+        pins.write(&interface, Bank::Low, |value, direction| {
+            *value = TMS;
+            *direction = TCK | TDI | TMS;
+        })
+        .await?;
+
+        let mut jtag = Jtag {
+            interface,
+            pins,
+            state: TapState::TestLogicReset,
+        };
+        jtag.reset().await?;
+
+        Ok(jtag)
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn into_inner(self) -> Interface {
+        self.interface
+    }
+
+    pub fn state(&self) -> TapState {
+        self.state
+    }
+
+    pub async fn set_adaptive_clocking(&self, enabled: bool) -> Result<()> {
+        if !enabled {
+            return self.interface.disable_adaptive_clocking().await;
+        }
+
+        self.pins.extend(RTCK as u16)?;
+        self.pins
+            .write(&self.interface, Bank::Low, |_, direction| {
+                *direction &= !RTCK;
+            })
+            .await?;
+        self.interface.enable_adaptive_clocking().await
+    }
+
+    pub async fn reset(&mut self) -> Result<()> {
+        let cmd = MpsseCmdBuilder::new().clock_tms_out(ClockEdge::Falling, 0x1f, 5, false);
+        self.interface.execute(cmd).await?;
+        self.state = TapState::TestLogicReset;
+
+        Ok(())
+    }
+
+    pub async fn goto(&mut self, state: TapState) -> Result<()> {
+        let cmd = self.move_to(MpsseCmdBuilder::new(), state);
+        self.interface.execute(cmd).await?;
+
+        Ok(())
+    }
+
+    pub async fn run_test(&mut self, cycles: usize) -> Result<()> {
+        let tms = if self.state == TapState::TestLogicReset {
+            0x7f
+        } else {
+            0x00
+        };
+
+        let mut cmd = MpsseCmdBuilder::new();
+        for chunk in (0..cycles).step_by(7) {
+            let count = (cycles - chunk).min(7) as u8;
+            cmd = cmd.clock_tms_out(ClockEdge::Falling, tms, count, false);
+        }
+        self.interface.execute(cmd).await?;
+
+        Ok(())
+    }
+
+    pub async fn shift_ir(&mut self, tdi: &[u8], bits: usize, end: TapState) -> Result<Vec<u8>> {
+        self.shift(TapState::ShiftIr, tdi, bits, end).await
+    }
+
+    pub async fn shift_dr(&mut self, tdi: &[u8], bits: usize, end: TapState) -> Result<Vec<u8>> {
+        self.shift(TapState::ShiftDr, tdi, bits, end).await
+    }
+
+    pub async fn ir_length(&mut self) -> Result<usize> {
+        let mut tdi = vec![0u8; MAX_IR_BITS / 8];
+        tdi.resize(MAX_IR_BITS / 4, 0xff);
+
+        let tdo = self
+            .shift_ir(&tdi, MAX_IR_BITS * 2, TapState::RunTestIdle)
+            .await?;
+
+        (MAX_IR_BITS..MAX_IR_BITS * 2)
+            .find(|&bit| tdo[bit / 8] & (1 << (bit % 8)) != 0)
+            .map(|bit| bit - MAX_IR_BITS)
+            .ok_or(Error::InvalidResponse(Vec::new()))
+    }
+
+    async fn shift(
+        &mut self,
+        target: TapState,
+        tdi: &[u8],
+        bits: usize,
+        end: TapState,
+    ) -> Result<Vec<u8>> {
+        if bits == 0 || tdi.len() < bits.div_ceil(8) {
+            return Err(Error::InvalidParameter(format!(
+                "shifting {} bits requires at least {} bytes of data, got {}",
+                bits,
+                bits.div_ceil(8),
+                tdi.len()
+            )));
+        }
+
+        let body = bits - 1;
+        let whole = body / 8;
+        let tail = (body % 8) as u8;
+        let last = tdi[body / 8] & (1 << (body % 8)) != 0;
+
+        let mut cmd = self
+            .move_to(MpsseCmdBuilder::new(), target)
+            .clock_bytes_inout(ClockEdge::Falling, BitOrder::LsbFirst, &tdi[..whole]);
+        if tail > 0 {
+            cmd = cmd.clock_bits_inout(ClockEdge::Falling, BitOrder::LsbFirst, tdi[whole], tail);
+        }
+        cmd = cmd.clock_tms_inout(ClockEdge::Falling, 0x01, 1, last);
+        self.state = target.next(true);
+        let cmd = self.move_to(cmd, end);
+
+        let res = self.interface.execute(cmd).await?;
+
+        let mut tdo = res[..whole].to_vec();
+        if tail > 0 {
+            tdo.push(res[whole] >> (8 - tail));
+        }
+        tdo.resize(bits.div_ceil(8), 0);
+        tdo[body / 8] |= (res[res.len() - 1] >> 7) << (body % 8);
+
+        Ok(tdo)
+    }
+
+    fn move_to(&mut self, cmd: MpsseCmdBuilder, state: TapState) -> MpsseCmdBuilder {
+        let (tms, count) = self.state.path(state);
+        self.state = state;
+
+        if count == 0 {
+            cmd
+        } else {
+            cmd.clock_tms_out(ClockEdge::Falling, tms, count, false)
+        }
+    }
+}
+
+pub async fn scan_chain(jtag: &mut Jtag) -> Result<Vec<TapInfo>> {
+    jtag.reset().await?;
+
+    let bits = IDCODE_BITS * (MAX_CHAIN_DEVICES + 1);
+    let tdi = vec![0xff; bits / 8];
+    let tdo = jtag.shift_dr(&tdi, bits, TapState::RunTestIdle).await?;
+    jtag.reset().await?;
+
+    let bit = |n: usize| tdo[n / 8] & (1 << (n % 8)) != 0;
+    let mut taps = Vec::new();
+    let mut pos = 0;
+
+    while pos + IDCODE_BITS <= bits {
+        if !bit(pos) {
+            taps.push(TapInfo { idcode: None });
+            pos += 1;
+            continue;
+        }
+
+        let idcode = (0..IDCODE_BITS).fold(0u32, |id, i| id | ((bit(pos + i) as u32) << i));
+        if idcode == u32::MAX {
+            return Ok(taps);
+        }
+
+        taps.push(TapInfo {
+            idcode: Some(idcode),
+        });
+        pos += IDCODE_BITS;
+    }
+
+    Err(Error::InvalidResponse(tdo))
+}
@@ -0,0 +1,468 @@
+use std::io::BufRead;
+use std::time::Duration;
+
+use super::{Jtag, TapState};
+use crate::mpsse::MpsseInterface;
+use crate::{Error, Result};
+
+#[derive(Clone, Debug, Default)]
+struct Pattern {
+    bits: usize,
+    tdi: Vec<u8>,
+    tdo: Option<Vec<u8>>,
+    mask: Vec<u8>,
+}
+
+impl Pattern {
+    fn update(&mut self, line: usize, args: &[String]) -> Result<()> {
+        let bits = parse_number(line, args.first())? as usize;
+        if bits != self.bits {
+            *self = Pattern {
+                bits,
+                tdi: vec![0; bits.div_ceil(8)],
+                tdo: None,
+                mask: ones(bits),
+            };
+        }
+        self.tdo = None;
+
+        for pair in args[1..].chunks(2) {
+            let [key, value] = pair else {
+                return Err(parse_error(line, "missing scan pattern value"));
+            };
+            let value = parse_hex(line, value, bits)?;
+
+            match key.as_str() {
+                "TDI" => self.tdi = value,
+                "TDO" => self.tdo = Some(value),
+                "MASK" => self.mask = value,
+                "SMASK" => {}
+                _ => return Err(parse_error(line, format!("unknown scan field {}", key))),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Player {
+    sir: Pattern,
+    sdr: Pattern,
+    hir: Pattern,
+    hdr: Pattern,
+    tir: Pattern,
+    tdr: Pattern,
+    end_ir: TapState,
+    end_dr: TapState,
+    run_state: TapState,
+    run_end: TapState,
+}
+
+impl Player {
+    async fn execute(&mut self, jtag: &mut Jtag, line: usize, stmt: &[String]) -> Result<()> {
+        let Some((command, args)) = stmt.split_first() else {
+            return Ok(());
+        };
+
+        match command.as_str() {
+            "SIR" => self.sir.update(line, args)?,
+            "SDR" => self.sdr.update(line, args)?,
+            "HIR" => self.hir.update(line, args)?,
+            "HDR" => self.hdr.update(line, args)?,
+            "TIR" => self.tir.update(line, args)?,
+            "TDR" => self.tdr.update(line, args)?,
+            "ENDIR" => self.end_ir = parse_state(line, args.first())?,
+            "ENDDR" => self.end_dr = parse_state(line, args.first())?,
+            "STATE" => {
+                for arg in args {
+                    jtag.goto(parse_state(line, Some(arg))?).await?;
+                }
+            }
+            "RUNTEST" => return self.run_test(jtag, line, args).await,
+            "FREQUENCY" => {
+                if let Some(arg) = args.first() {
+                    let hz = parse_number(line, Some(arg))?;
+                    jtag.interface().set_frequency(hz as u32).await?;
+                }
+            }
+            "TRST" => {}
+            _ => {
+                return Err(parse_error(
+                    line,
+                    format!("unsupported command {}", command),
+                ))
+            }
+        }
+
+        match command.as_str() {
+            "SIR" => {
+                let end = self.end_ir;
+                shift(jtag, line, [&self.hir, &self.sir, &self.tir], true, end).await
+            }
+            "SDR" => {
+                let end = self.end_dr;
+                shift(jtag, line, [&self.hdr, &self.sdr, &self.tdr], false, end).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn run_test(&mut self, jtag: &mut Jtag, line: usize, args: &[String]) -> Result<()> {
+        let mut args = args.iter().peekable();
+
+        if let Some(state) = args.peek().and_then(|arg| state_from_name(arg)) {
+            self.run_state = state;
+            self.run_end = state;
+            args.next();
+        }
+
+        let mut cycles = 0;
+        let mut min_time = Duration::ZERO;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "MAXIMUM" => {
+                    args.next();
+                    args.next();
+                }
+                "ENDSTATE" => self.run_end = parse_state(line, args.next())?,
+                _ => {
+                    let value = parse_float(line, arg)?;
+                    match args.next().map(String::as_str) {
+                        Some("TCK") | Some("SCK") => cycles = value as usize,
+                        Some("SEC") => min_time = Duration::from_secs_f64(value),
+                        _ => return Err(parse_error(line, "malformed RUNTEST")),
+                    }
+                }
+            }
+        }
+
+        jtag.goto(self.run_state).await?;
+        jtag.run_test(cycles).await?;
+        if !min_time.is_zero() {
+            futures_timer::Delay::new(min_time).await;
+        }
+        jtag.goto(self.run_end).await
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player {
+            sir: Pattern::default(),
+            sdr: Pattern::default(),
+            hir: Pattern::default(),
+            hdr: Pattern::default(),
+            tir: Pattern::default(),
+            tdr: Pattern::default(),
+            end_ir: TapState::RunTestIdle,
+            end_dr: TapState::RunTestIdle,
+            run_state: TapState::RunTestIdle,
+            run_end: TapState::RunTestIdle,
+        }
+    }
+}
+
+pub async fn play(reader: impl BufRead, jtag: &mut Jtag) -> Result<()> {
+    let mut player = Player::default();
+    let mut statements = Statements::default();
+
+    for (n, text) in reader.lines().enumerate() {
+        for (start, tokens) in statements.push(n + 1, &text?) {
+            player.execute(jtag, start, &tokens).await?;
+        }
+    }
+
+    statements.finish()
+}
+
+#[derive(Debug, Default)]
+struct Statements {
+    stmt: String,
+    start: usize,
+}
+
+impl Statements {
+    fn push(&mut self, line: usize, text: &str) -> Vec<(usize, Vec<String>)> {
+        let text = match text.find('!').into_iter().chain(text.find("//")).min() {
+            Some(pos) => &text[..pos],
+            None => text,
+        };
+
+        let mut complete = Vec::new();
+        let mut parts = text.split(';').peekable();
+        while let Some(part) = parts.next() {
+            if self.stmt.trim().is_empty() {
+                self.start = line;
+            }
+            self.stmt.push_str(part);
+            self.stmt.push(' ');
+
+            if parts.peek().is_some() {
+                complete.push((self.start, tokenize(&self.stmt)));
+                self.stmt.clear();
+            }
+        }
+
+        complete
+    }
+
+    fn finish(&self) -> Result<()> {
+        if !self.stmt.trim().is_empty() {
+            return Err(parse_error(self.start, "unterminated statement"));
+        }
+
+        Ok(())
+    }
+}
+
+async fn shift(
+    jtag: &mut Jtag,
+    line: usize,
+    parts: [&Pattern; 3],
+    ir: bool,
+    end: TapState,
+) -> Result<()> {
+    let bits: usize = parts.iter().map(|p| p.bits).sum();
+    if bits == 0 {
+        return Ok(());
+    }
+
+    let tdi = concat(parts.map(|p| (&p.tdi[..], p.bits)));
+    let tdo = if ir {
+        jtag.shift_ir(&tdi, bits, end).await?
+    } else {
+        jtag.shift_dr(&tdi, bits, end).await?
+    };
+
+    if parts.iter().all(|p| p.tdo.is_none()) {
+        return Ok(());
+    }
+
+    let zeros = vec![0u8; bits.div_ceil(8)];
+    let expected = concat(parts.map(|p| (p.tdo.as_deref().unwrap_or(&zeros), p.bits)));
+    let mask = concat(parts.map(|p| match p.tdo {
+        Some(_) => (&p.mask[..], p.bits),
+        None => (&zeros[..], p.bits),
+    }));
+
+    let mismatch = tdo
+        .iter()
+        .zip(&expected)
+        .zip(&mask)
+        .any(|((actual, expected), mask)| (actual ^ expected) & mask != 0);
+    if mismatch {
+        return Err(Error::SvfMismatch {
+            line,
+            expected,
+            actual: tdo,
+        });
+    }
+
+    Ok(())
+}
+
+fn tokenize(stmt: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut rest = stmt;
+
+    while let Some(pos) = rest.find('(') {
+        tokens.extend(rest[..pos].split_whitespace().map(str::to_uppercase));
+        let end = rest[pos..].find(')').map_or(rest.len(), |end| pos + end);
+        tokens.push(rest[pos + 1..end].split_whitespace().collect());
+        rest = rest.get(end + 1..).unwrap_or_default();
+    }
+    tokens.extend(rest.split_whitespace().map(str::to_uppercase));
+
+    tokens
+}
+
+fn concat<const N: usize>(parts: [(&[u8], usize); N]) -> Vec<u8> {
+    let total: usize = parts.iter().map(|&(_, bits)| bits).sum();
+    let mut out = vec![0u8; total.div_ceil(8)];
+    let mut pos = 0;
+
+    for (data, bits) in parts {
+        for bit in 0..bits {
+            if data[bit / 8] & (1 << (bit % 8)) != 0 {
+                out[pos / 8] |= 1 << (pos % 8);
+            }
+            pos += 1;
+        }
+    }
+
+    out
+}
+
+fn ones(bits: usize) -> Vec<u8> {
+    let mut out = vec![0xff; bits.div_ceil(8)];
+    if bits % 8 != 0 {
+        if let Some(last) = out.last_mut() {
+            *last = (1 << (bits % 8)) - 1;
+        }
+    }
+
+    out
+}
+
+fn parse_hex(line: usize, value: &str, bits: usize) -> Result<Vec<u8>> {
+    let digits = value.as_bytes();
+    let mut out = vec![0u8; bits.div_ceil(8)];
+
+    for (i, &digit) in digits.iter().rev().enumerate() {
+        let nibble = (digit as char)
+            .to_digit(16)
+            .ok_or_else(|| parse_error(line, format!("invalid hex value {}", value)))?;
+        if let Some(byte) = out.get_mut(i / 2) {
+            *byte |= (nibble as u8) << ((i % 2) * 4);
+        }
+    }
+
+    if bits % 8 != 0 {
+        if let Some(last) = out.last_mut() {
+            *last &= (1 << (bits % 8)) - 1;
+        }
+    }
+
+    Ok(out)
+}
+
+fn parse_number(line: usize, arg: Option<&String>) -> Result<f64> {
+    match arg {
+        Some(arg) => parse_float(line, arg),
+        None => Err(parse_error(line, "missing numeric argument")),
+    }
+}
+
+fn parse_float(line: usize, arg: &str) -> Result<f64> {
+    arg.parse()
+        .map_err(|_| parse_error(line, format!("invalid number {}", arg)))
+}
+
+fn parse_state(line: usize, arg: Option<&String>) -> Result<TapState> {
+    arg.and_then(|arg| state_from_name(arg))
+        .ok_or_else(|| parse_error(line, format!("invalid state {:?}", arg)))
+}
+
+fn state_from_name(name: &str) -> Option<TapState> {
+    Some(match name {
+        "RESET" => TapState::TestLogicReset,
+        "IDLE" => TapState::RunTestIdle,
+        "DRSELECT" => TapState::SelectDrScan,
+        "DRCAPTURE" => TapState::CaptureDr,
+        "DRSHIFT" => TapState::ShiftDr,
+        "DREXIT1" => TapState::Exit1Dr,
+        "DRPAUSE" => TapState::PauseDr,
+        "DREXIT2" => TapState::Exit2Dr,
+        "DRUPDATE" => TapState::UpdateDr,
+        "IRSELECT" => TapState::SelectIrScan,
+        "IRCAPTURE" => TapState::CaptureIr,
+        "IRSHIFT" => TapState::ShiftIr,
+        "IREXIT1" => TapState::Exit1Ir,
+        "IRPAUSE" => TapState::PauseIr,
+        "IREXIT2" => TapState::Exit2Ir,
+        "IRUPDATE" => TapState::UpdateIr,
+        _ => return None,
+    })
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> Error {
+    Error::Svf {
+        line,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn multi_line_statements() {
+        let mut statements = Statements::default();
+
+        assert!(statements
+            .push(1, "SDR 8 TDI (a5) ! trailing comment")
+            .is_empty());
+        assert_eq!(
+            statements.push(2, "  TDO (5a) MASK (ff); RUNTEST 10 TCK;"),
+            [
+                (
+                    1,
+                    tokens(&["SDR", "8", "TDI", "a5", "TDO", "5a", "MASK", "ff"])
+                ),
+                (2, tokens(&["RUNTEST", "10", "TCK"])),
+            ]
+        );
+        assert!(statements.finish().is_ok());
+
+        assert!(statements.push(3, "enddr idle // lower case").is_empty());
+        assert!(matches!(
+            statements.finish(),
+            Err(Error::Svf { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn parenthesised_hex_with_whitespace() {
+        assert_eq!(
+            tokenize("sir 16 tdi (12 34)  smask ( ffff ) "),
+            tokens(&["SIR", "16", "TDI", "1234", "SMASK", "ffff"])
+        );
+
+        let mut statements = Statements::default();
+        assert!(statements.push(1, "SDR 24 TDI (12").is_empty());
+        assert_eq!(
+            statements.push(2, "  34\t56);"),
+            [(1, tokens(&["SDR", "24", "TDI", "123456"]))]
+        );
+    }
+
+    #[test]
+    fn odd_bit_counts() {
+        assert_eq!(parse_hex(1, "1ff", 9).unwrap(), [0xff, 0x01]);
+        assert_eq!(parse_hex(1, "fff", 9).unwrap(), [0xff, 0x01]);
+        assert_eq!(parse_hex(1, "7", 3).unwrap(), [0x07]);
+        assert_eq!(parse_hex(1, "F", 3).unwrap(), [0x07]);
+        assert!(parse_hex(1, "xyz", 9).is_err());
+        assert_eq!(ones(9), [0xff, 0x01]);
+
+        assert_eq!(
+            concat([(&[0x05][..], 3), (&[0x01, 0x01][..], 9)]),
+            [0x0d, 0x08]
+        );
+        assert_eq!(concat([(&[0x01][..], 1), (&[0x7f][..], 7)]), [0xff]);
+    }
+
+    #[test]
+    fn mask_persists_across_scans() {
+        let mut pattern = Pattern::default();
+
+        pattern
+            .update(1, &tokenize("8 TDI (00) TDO (a5) MASK (03)"))
+            .unwrap();
+        assert_eq!(pattern.tdo.as_deref(), Some(&[0xa5][..]));
+        assert_eq!(pattern.mask, [0x03]);
+
+        pattern.update(2, &tokenize("8 TDI (ff)")).unwrap();
+        assert_eq!(pattern.tdi, [0xff]);
+        assert_eq!(pattern.tdo, None);
+        assert_eq!(pattern.mask, [0x03]);
+
+        pattern.update(3, &tokenize("8 TDO (3c)")).unwrap();
+        assert_eq!(pattern.tdi, [0xff]);
+        assert_eq!(pattern.tdo.as_deref(), Some(&[0x3c][..]));
+        assert_eq!(pattern.mask, [0x03]);
+
+        pattern.update(4, &tokenize("4 TDI (5)")).unwrap();
+        assert_eq!(pattern.tdi, [0x05]);
+        assert_eq!(pattern.tdo, None);
+        assert_eq!(pattern.mask, [0x0f]);
+        assert_eq!(pattern.bits, 4);
+    }
+}
@@ -1,24 +1,160 @@
+pub mod bitbang;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cbus;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod eeprom;
+mod error;
+pub mod fifo;
+pub mod gpio;
+mod hotplug;
+pub mod i2c;
+pub mod jtag;
+mod line;
+pub mod mcu;
 pub mod mpsse;
+pub mod onewire;
+mod packet;
+mod pins;
+mod pool;
+pub mod programmers;
+pub mod protocol;
+mod reader;
+mod reconnect;
+pub mod rs485;
+pub mod selftest;
+pub mod serial;
+pub mod spi;
+mod split;
+mod stats;
+pub mod swd;
+#[cfg(feature = "trace")]
+pub mod trace;
+pub mod transport;
+mod uart;
+use async_lock::Mutex;
+use core::future::Future;
 use core::time::Duration;
+use std::io::IoSlice;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
-use anyhow::Result;
+pub use error::{Error, Result};
+pub use hotplug::{watch_devices, watch_devices_with_filter, DeviceEvent, DeviceWatcher};
+pub use line::LineEvent;
+pub use mpsse::{ClockOutput, Mpsse, MpsseSettings};
+pub use packet::{FtdiPacketDecoder, Packet};
+pub use pins::PinClaim;
+pub use reconnect::ReconnectingInterface;
+pub use split::{ReadHalf, WriteHalf};
+pub use stats::Stats;
+pub use transport::{BulkEndpoint, Transport};
+pub use uart::{SoftwareFlowControl, Uart};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(u8)]
+pub enum FlowControl {
+    None = 0x00,
+    RtsCts = 0x01,
+    DtrDsr = 0x02,
+    XonXoff = 0x04,
+}
 
-pub enum Error {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DataBits {
+    Seven = 7,
+    Eight = 8,
+}
 
-pub struct MpsseInterface {}
-pub struct UartInterface {}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Parity {
+    None = 0,
+    Odd = 1,
+    Even = 2,
+    Mark = 3,
+    Space = 4,
+}
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
-pub enum FlowControl {
-    None,
-    RtsCts,
-    DtrDsr,
-    XonXoff,
+pub enum StopBits {
+    One = 0,
+    OnePointFive = 1,
+    Two = 2,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineSettings {
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
 }
 
+impl Default for LineSettings {
+    fn default() -> Self {
+        LineSettings {
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+impl LineSettings {
+    fn value(&self, break_on: bool) -> u16 {
+        self.data_bits as u16
+            | (self.parity as u16) << 8
+            | (self.stop_bits as u16) << 11
+            | (break_on as u16) << 14
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ModemStatus {
+    pub cts: bool,
+    pub dsr: bool,
+    pub ri: bool,
+    pub rlsd: bool,
+    pub data_ready: bool,
+    pub overrun_error: bool,
+    pub parity_error: bool,
+    pub framing_error: bool,
+    pub break_interrupt: bool,
+    pub thre: bool,
+    pub temt: bool,
+    pub fifo_error: bool,
+}
+
+impl ModemStatus {
+    pub fn from_bytes(status: [u8; 2]) -> Self {
+        let [modem, line] = status;
+
+        ModemStatus {
+            cts: modem & 0x10 != 0,
+            dsr: modem & 0x20 != 0,
+            ri: modem & 0x40 != 0,
+            rlsd: modem & 0x80 != 0,
+            data_ready: line & 0x01 != 0,
+            overrun_error: line & 0x02 != 0,
+            parity_error: line & 0x04 != 0,
+            framing_error: line & 0x08 != 0,
+            break_interrupt: line & 0x10 != 0,
+            thre: line & 0x20 != 0,
+            temt: line & 0x40 != 0,
+            fifo_error: line & 0x80 != 0,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Bitmode {
@@ -53,6 +189,7 @@ pub enum ControlRequest {
     EraseEeprom = 0x92,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, Debug)]
 pub enum InterfaceType {
     Mpsse,
@@ -67,17 +204,176 @@ pub struct InterfaceInfo {
     pub kind: InterfaceType,
 }
 
+const DEFAULT_READ_QUEUE_DEPTH: usize = 4;
+const WRITE_CHUNK_PACKETS: usize = 32;
+const FULL_SPEED_PACKET_SIZE: usize = 64;
+const HIGH_SPEED_PACKET_SIZE: usize = 512;
+const WRITE_QUEUE_DEPTH: usize = 4;
+const PURGE_MAX_READS: usize = 64;
+const CONTROL_RETRY_BACKOFF: Duration = Duration::from_millis(10);
+
+pub const XON: u8 = 0x11;
+pub const XOFF: u8 = 0x13;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PerformanceProfile {
+    Latency,
+    Balanced,
+    Throughput,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub control_timeout: Duration,
+    pub io_timeout: Option<Duration>,
+    pub retries: u32,
+    pub baudrate_tolerance: Option<u32>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            control_timeout: Duration::from_millis(100),
+            io_timeout: None,
+            retries: 2,
+            baudrate_tolerance: Some(3),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterfaceConfig {
+    pub baudrate: Option<u32>,
+    pub flow_control: Option<FlowControl>,
+    pub line_settings: LineSettings,
+    pub latency_timer: Duration,
+    pub read_timeout: Option<Duration>,
+    pub write_timeout: Option<Duration>,
+    pub read_queue_depth: usize,
+    pub read_transfer_packets: usize,
+    pub config: Config,
+}
+
 #[derive(Clone)]
 pub struct Interface {
     pub read_buffer: Arc<Mutex<(Vec<u8>, usize)>>,
-    pub dev: nusb::Device,
-    pub dev_info: nusb::DeviceInfo,
+    line_settings: Arc<Mutex<(LineSettings, bool)>>,
+    background_reader: Arc<Mutex<Option<Arc<reader::BackgroundReader>>>>,
+    read_queue_depth: Arc<AtomicUsize>,
+    read_transfer_packets: Arc<AtomicUsize>,
+    read_pool: Arc<pool::BufferPool>,
+    timeouts: Arc<std::sync::Mutex<(Option<Duration>, Option<Duration>)>>,
+    config: Arc<std::sync::Mutex<Config>>,
+    event_char: Arc<std::sync::Mutex<Option<u8>>>,
+    baudrate: Arc<std::sync::Mutex<Option<u32>>>,
+    flow_control: Arc<std::sync::Mutex<Option<(FlowControl, u16)>>>,
+    latency_timer: Arc<std::sync::Mutex<Option<Duration>>>,
+    line_monitor: Arc<line::LineMonitor>,
+    edge_poller: Arc<gpio::EdgePoller>,
+    counters: Arc<stats::Counters>,
+    connection: Arc<Connection>,
+    pins: Arc<pins::PinRegistry>,
+    bitmode: Arc<std::sync::Mutex<(u8, Bitmode)>>,
+    mpsse_state: Arc<std::sync::Mutex<mpsse::MpsseState>>,
+    command_lock: Arc<Mutex<()>>,
+    close_guard: Arc<CloseGuard>,
+    pub dev: Option<nusb::Device>,
+    pub dev_info: Option<nusb::DeviceInfo>,
     pub device_type: DeviceType,
     pub num: u8,
     pub max_packet_size: usize,
-    interface: nusb::Interface,
-    ep_in: Arc<Mutex<nusb::Endpoint<Bulk, In>>>,
-    ep_out: Arc<Mutex<nusb::Endpoint<Bulk, Out>>>,
+    transport: Arc<dyn Transport>,
+    ep_in: Arc<Mutex<Box<dyn BulkEndpoint>>>,
+    ep_out: Arc<Mutex<Box<dyn BulkEndpoint>>>,
+}
+
+#[derive(Debug, Default)]
+struct Connection {
+    disconnected: AtomicBool,
+    event: event_listener::Event,
+}
+
+impl Connection {
+    fn observe<T>(&self, res: &Result<T, TransferError>) {
+        if let Err(TransferError::Disconnected) = res {
+            self.disconnected.store(true, Ordering::Release);
+            self.event.notify(usize::MAX);
+        }
+    }
+
+    fn ensure_connected(&self) -> Result<()> {
+        match self.disconnected.load(Ordering::Acquire) {
+            true => Err(Error::Disconnected),
+            false => Ok(()),
+        }
+    }
+}
+
+struct CloseGuard {
+    transport: Arc<dyn Transport>,
+    num: u8,
+    purge_on_close: AtomicBool,
+    reset_on_close: AtomicBool,
+    closed: AtomicBool,
+    connection: Arc<Connection>,
+    config: Arc<std::sync::Mutex<Config>>,
+}
+
+impl Drop for CloseGuard {
+    fn drop(&mut self) {
+        if self.closed.load(Ordering::Relaxed)
+            || self.connection.disconnected.load(Ordering::Acquire)
+        {
+            return;
+        }
+
+        #[cfg(feature = "trace")]
+        tracing::warn!(num = self.num, "interface dropped without close");
+
+        let transport = self.transport.clone();
+        let num = self.num;
+        let reset = self.reset_on_close.load(Ordering::Relaxed);
+        let purge = self.purge_on_close.load(Ordering::Relaxed);
+        let timeout = self.config.lock().unwrap().control_timeout;
+        std::thread::spawn(move || {
+            let _ =
+                futures_lite::future::block_on(release(&*transport, num, reset, purge, timeout));
+        });
+    }
+}
+
+async fn release(
+    transport: &dyn Transport,
+    num: u8,
+    reset: bool,
+    purge: bool,
+    timeout: Duration,
+) -> Result<()> {
+    let mut requests = vec![];
+    if reset {
+        requests.push((ControlRequest::SetBitmode, (Bitmode::Reset as u16) << 8));
+    }
+    if purge {
+        requests.push((ControlRequest::Reset, protocol::RESET_PURGE_RX));
+        requests.push((ControlRequest::Reset, protocol::RESET_PURGE_TX));
+    }
+
+    for (request, value) in requests {
+        let pkt = ControlOut {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: request as u8,
+            value,
+            index: protocol::channel_index(num),
+            data: &[],
+        };
+
+        transport.control_out(pkt, timeout).await?;
+    }
+
+    Ok(())
 }
 
 impl core::fmt::Debug for Interface {
@@ -90,22 +386,279 @@ impl core::fmt::Debug for Interface {
 }
 
 use nusb::transfer::*;
+use transport::UsbTransport;
 
 impl Interface {
-    pub async fn with_serial_number(sn: &str, port: u8) -> Result<Self> {
-        let mut int = list_interfaces()
+    pub async fn with_serial_number(sn: &str, port: PortLetter) -> Result<Self> {
+        list_devices()
             .await?
-            .find(|i| i.dev.serial_number().map_or(false, |_sn| _sn == sn))
-            .ok_or_else(|| anyhow::Error::msg("device not found"))?;
+            .find(|dev| dev.dev.serial_number().map_or(false, |_sn| _sn == sn))
+            .ok_or(Error::DeviceNotFound)?
+            .open_port(port)
+            .await
+    }
+
+    pub fn with_transport(
+        transport: Arc<dyn Transport>,
+        device_type: DeviceType,
+        num: u8,
+    ) -> Result<Self> {
+        let ep_in = transport.bulk_in(num)?;
+        let ep_out = transport.bulk_out(num)?;
+        let max_packet_size = ep_in.max_packet_size();
+
+        Ok(Interface::from_parts(
+            transport,
+            ep_in,
+            ep_out,
+            device_type,
+            num,
+            max_packet_size,
+        ))
+    }
+
+    fn from_parts(
+        transport: Arc<dyn Transport>,
+        ep_in: Box<dyn BulkEndpoint>,
+        ep_out: Box<dyn BulkEndpoint>,
+        device_type: DeviceType,
+        num: u8,
+        max_packet_size: usize,
+    ) -> Self {
+        let connection = Arc::new(Connection::default());
+        let config: Arc<std::sync::Mutex<Config>> = Arc::default();
+        let close_guard = Arc::new(CloseGuard {
+            transport: transport.clone(),
+            num,
+            purge_on_close: AtomicBool::new(false),
+            reset_on_close: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            connection: connection.clone(),
+            config: config.clone(),
+        });
+
+        Interface {
+            dev: None,
+            dev_info: None,
+            device_type,
+            transport,
+            num,
+            max_packet_size,
+            read_buffer: Arc::default(),
+            line_settings: Arc::default(),
+            background_reader: Arc::default(),
+            read_queue_depth: Arc::new(AtomicUsize::new(DEFAULT_READ_QUEUE_DEPTH)),
+            read_transfer_packets: Arc::new(AtomicUsize::new(1)),
+            read_pool: Arc::default(),
+            timeouts: Arc::default(),
+            config,
+            event_char: Arc::default(),
+            baudrate: Arc::default(),
+            flow_control: Arc::default(),
+            latency_timer: Arc::default(),
+            line_monitor: Arc::default(),
+            edge_poller: Arc::default(),
+            counters: Arc::default(),
+            connection,
+            pins: Arc::default(),
+            bitmode: Arc::new(std::sync::Mutex::new((0, Bitmode::Reset))),
+            mpsse_state: Arc::default(),
+            command_lock: Arc::default(),
+            close_guard,
+            ep_in: Arc::new(Mutex::new(ep_in)),
+            ep_out: Arc::new(Mutex::new(ep_out)),
+        }
+    }
+
+    pub fn set_purge_on_close(&self, purge: bool) {
+        self.close_guard
+            .purge_on_close
+            .store(purge, Ordering::Relaxed);
+    }
+
+    pub fn set_reset_on_close(&self, reset: bool) {
+        self.close_guard
+            .reset_on_close
+            .store(reset, Ordering::Relaxed);
+    }
+
+    pub async fn close(self) -> Result<()> {
+        self.stop_background_reader().await?;
+
+        let guard = &self.close_guard;
+        guard.closed.store(true, Ordering::Relaxed);
+        release(
+            &*self.transport,
+            self.num,
+            guard.reset_on_close.load(Ordering::Relaxed),
+            guard.purge_on_close.load(Ordering::Relaxed),
+            self.config().control_timeout,
+        )
+        .await
+    }
+
+    pub async fn close_keep_state(self) -> Result<()> {
+        self.set_reset_on_close(false);
+        self.close().await
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.connection.disconnected.load(Ordering::Acquire)
+    }
+
+    pub async fn closed(&self) {
+        loop {
+            let listener = self.connection.event.listen();
+            if self.is_disconnected() {
+                return;
+            }
+
+            listener.await;
+        }
+    }
+
+    pub fn claim_pins(&self, owner: &'static str, mask: u16) -> Result<PinClaim> {
+        let claim = self.pins.claim(owner);
+        claim.extend(mask)?;
+
+        Ok(claim)
+    }
+
+    pub fn pin_owner(&self, pin: u8) -> Option<&'static str> {
+        self.pins.owner(pin)
+    }
+
+    pub fn split(self) -> (ReadHalf, WriteHalf) {
+        split::split(self)
+    }
+
+    pub async fn into_mpsse(self) -> Result<Mpsse> {
+        Mpsse::new(self).await
+    }
+
+    pub async fn into_uart(self) -> Result<Uart> {
+        self.set_bitmode(0, Bitmode::Reset).await?;
+        self.purge_all().await?;
+
+        Ok(Uart::new(self))
+    }
+
+    pub async fn set_flow_control(&self, flow_control: FlowControl) -> Result<()> {
+        let value = match flow_control {
+            FlowControl::XonXoff => u16::from_le_bytes([XON, XOFF]),
+            _ => 0,
+        };
+
+        self.write_flow_control(flow_control, value).await
+    }
+
+    pub async fn set_xon_xoff(&self, xon: u8, xoff: u8) -> Result<()> {
+        if xon == xoff {
+            return Err(Error::InvalidParameter(format!(
+                "xon and xoff characters must differ, both are {:#04x}",
+                xon
+            )));
+        }
+
+        self.write_flow_control(FlowControl::XonXoff, u16::from_le_bytes([xon, xoff]))
+            .await
+    }
+
+    async fn write_flow_control(&self, flow_control: FlowControl, value: u16) -> Result<()> {
+        let pkt = ControlOut {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::SetFlowControl as u8,
+            value,
+            index: ((flow_control as u16) << 8) | self.channel_index(),
+            data: &[],
+        };
+
+        self.control_out(pkt).await?;
+        *self.flow_control.lock().unwrap() = Some((flow_control, value));
 
-        int.open().await
+        Ok(())
+    }
+
+    pub async fn set_baudrate(&self, baudrate: u32) -> Result<u32> {
+        if baudrate == 0 {
+            return Err(Error::InvalidParameter("baudrate must be non-zero".into()));
+        }
+
+        let high_speed = matches!(
+            self.device_type,
+            DeviceType::FT2232H | DeviceType::FT4232H | DeviceType::FT232H
+        );
+        let max_baudrate = if high_speed { 12_000_000 } else { 3_000_000 };
+        if baudrate > max_baudrate {
+            return Err(Error::InvalidParameter(format!(
+                "baudrate {} exceeds the {} baud maximum of {:?}",
+                baudrate, max_baudrate, self.device_type
+            )));
+        }
+
+        let (encoded, actual) = if high_speed && baudrate > 12_000_000 / 0x3fff {
+            let (encoded, actual) = baudrate_clkbits(baudrate, 120_000_000, 10);
+            (encoded | protocol::BAUD_HIGH_SPEED_CLOCK, actual)
+        } else {
+            baudrate_clkbits(baudrate, 48_000_000, 16)
+        };
+
+        if let Some(tolerance) = self.config().baudrate_tolerance {
+            let deviation = (actual as u64).abs_diff(baudrate as u64) * 100;
+            if deviation > tolerance as u64 * baudrate as u64 {
+                return Err(Error::BaudrateDeviation {
+                    requested: baudrate,
+                    actual,
+                });
+            }
+        }
+
+        let index = protocol::baudrate_index(self.device_type, encoded, self.num);
+
+        let pkt = ControlOut {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::SetBaudrate as u8,
+            value: encoded as u16,
+            index,
+            data: &[],
+        };
+
+        self.control_out(pkt).await?;
+        *self.baudrate.lock().unwrap() = Some(baudrate);
+
+        Ok(actual)
+    }
+
+    pub async fn set_line_settings(&self, settings: LineSettings) -> Result<()> {
+        let mut line = self.line_settings.lock().await;
+        self.set_data(settings, line.1).await?;
+        line.0 = settings;
+
+        Ok(())
     }
 
-    pub fn set_flow_control(&self, _flow_control: FlowControl) -> Result<()> {
+    pub async fn set_break(&self, enable: bool) -> Result<()> {
+        let mut line = self.line_settings.lock().await;
+        self.set_data(line.0, enable).await?;
+        line.1 = enable;
+
         Ok(())
     }
 
-    pub fn set_baudrate(&self, _baudrate: u32) -> Result<()> {
+    async fn set_data(&self, settings: LineSettings, break_on: bool) -> Result<()> {
+        let pkt = ControlOut {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::SetData as u8,
+            value: settings.value(break_on),
+            index: self.channel_index(),
+            data: &[],
+        };
+
+        self.control_out(pkt).await?;
+
         Ok(())
     }
 
@@ -115,32 +668,36 @@ impl Interface {
             recipient: Recipient::Device,
             request: ControlRequest::GetLatencyTimer as u8,
             value: 0,
-            index: self.num as u16 + 1,
+            index: self.channel_index(),
             length: 1,
         };
 
-        let res = self
-            .interface
-            .control_in(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        let res = self.control_in(pkt).await?;
         let res = core::time::Duration::from_millis(res[0] as u64);
 
         Ok(res)
     }
 
     pub async fn set_latency_timer(&self, timer: core::time::Duration) -> Result<()> {
+        let value = timer.as_millis();
+        if !(1..=255).contains(&value) {
+            return Err(Error::InvalidParameter(format!(
+                "latency timer must be 1..=255 ms, got {} ms",
+                value
+            )));
+        }
+
         let pkt = ControlOut {
             control_type: ControlType::Vendor,
             recipient: Recipient::Device,
             request: ControlRequest::SetLatencyTimer as u8,
-            value: timer.as_millis() as u16,
-            index: self.num as u16 + 1,
+            value: value as u16,
+            index: self.channel_index(),
             data: &[],
         };
 
-        self.interface
-            .control_out(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        self.control_out(pkt).await?;
+        *self.latency_timer.lock().unwrap() = Some(timer);
 
         Ok(())
     }
@@ -150,38 +707,53 @@ impl Interface {
             control_type: ControlType::Vendor,
             recipient: Recipient::Device,
             request: ControlRequest::Reset as u8,
-            value: 0,
-            index: self.num as u16 + 1,
+            value: protocol::RESET_SIO,
+            index: self.channel_index(),
             data: &[],
         };
 
-        self.interface
-            .control_out(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        self.control_out(pkt).await?;
 
         Ok(())
     }
 
     pub async fn purge_rx(&self) -> Result<()> {
-        let index = self.num as u16 + 1;
         let pkt = ControlOut {
             control_type: ControlType::Vendor,
             recipient: Recipient::Device,
             request: ControlRequest::Reset as u8,
-            value: 1,
-            index,
+            value: protocol::RESET_PURGE_RX,
+            index: self.channel_index(),
             data: &[],
         };
 
-        self.interface
-            .control_out(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        self.control_out(pkt).await?;
+
+        if let Some(reader) = self.background_reader().await {
+            reader.clear();
+            return Ok(());
+        }
+
+        let mut oldbuf = self.read_buffer.lock().await;
+        oldbuf.0.clear();
+        oldbuf.1 = 0;
 
         let mut ep_in = self.ep_in.lock().await;
 
-        let buffer = ep_in.allocate(self.max_packet_size);
-        ep_in.submit(buffer);
-        ep_in.next_complete().await.status?;
+        for _ in 0..PURGE_MAX_READS {
+            self.fill_read_queue(&mut **ep_in);
+
+            let completion = ep_in.next_complete().await;
+            self.record_read(&completion);
+            completion.status?;
+
+            #[cfg(feature = "trace")]
+            trace::bulk_in(ep_in.endpoint_address(), &completion.buffer);
+
+            if self.packet_decoder().payload_len(&completion.buffer) == 0 {
+                break;
+            }
+        }
 
         Ok(())
     }
@@ -191,14 +763,12 @@ impl Interface {
             control_type: ControlType::Vendor,
             recipient: Recipient::Device,
             request: ControlRequest::Reset as u8,
-            value: 2,
-            index: self.num as u16 + 1,
+            value: protocol::RESET_PURGE_TX,
+            index: self.channel_index(),
             data: &[],
         };
 
-        self.interface
-            .control_out(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        self.control_out(pkt).await?;
 
         Ok(())
     }
@@ -218,48 +788,181 @@ impl Interface {
             recipient: Recipient::Device,
             request: ControlRequest::SetBitmode as u8,
             value,
-            index: self.num as u16 + 1,
+            index: self.channel_index(),
             data: &[],
         };
 
-        self.interface
-            .control_out(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        self.control_out(pkt).await?;
+        *self.bitmode.lock().unwrap() = (bitmask, bitmode);
+
+        Ok(())
+    }
+
+    pub fn current_bitmode(&self) -> (u8, Bitmode) {
+        *self.bitmode.lock().unwrap()
+    }
+
+    pub(crate) async fn restore_from(&mut self, previous: &Interface) -> Result<()> {
+        use mpsse::MpsseInterface;
+
+        self.pins = previous.pins.clone();
+
+        let baudrate = *previous.baudrate.lock().unwrap();
+        if let Some(baudrate) = baudrate {
+            self.set_baudrate(baudrate).await?;
+        }
+        let flow_control = *previous.flow_control.lock().unwrap();
+        if let Some((flow_control, value)) = flow_control {
+            self.write_flow_control(flow_control, value).await?;
+        }
+        let latency_timer = *previous.latency_timer.lock().unwrap();
+        if let Some(timer) = latency_timer {
+            self.set_latency_timer(timer).await?;
+        }
+        let (settings, break_on) = *previous.line_settings.lock().await;
+        self.set_data(settings, break_on).await?;
+        *self.line_settings.lock().await = (settings, break_on);
+
+        match previous.current_bitmode() {
+            (_, Bitmode::Reset) => return Ok(()),
+            (_, Bitmode::Mpsse) => {
+                let state = *previous.mpsse_state.lock().unwrap();
+                self.initialize_mpsse().await?;
+                state.replay(self).await?;
+            }
+            (bitmask, bitmode) => return self.set_bitmode(bitmask, bitmode).await,
+        }
+
+        let state = *self.pins.state.lock().await;
+        for (bank, (value, direction)) in [gpio::Bank::Low, gpio::Bank::High].into_iter().zip(state)
+        {
+            if (value, direction) != (0, 0) {
+                self.write_data_bits(bank, value, direction).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn require_bitmode(&self, expected: Bitmode) -> Result<()> {
+        let (_, actual) = self.current_bitmode();
+        if actual != expected {
+            return Err(Error::WrongBitmode { expected, actual });
+        }
 
         Ok(())
     }
 
-    pub async fn status(&self) -> Result<()> {
+    pub(crate) async fn write_mpsse(&self, cmd: Vec<u8>) -> Result<()> {
+        match self.current_bitmode() {
+            (_, Bitmode::Mpsse | Bitmode::Mcu) => {}
+            (_, actual) => {
+                return Err(Error::WrongBitmode {
+                    expected: Bitmode::Mpsse,
+                    actual,
+                })
+            }
+        }
+
+        self.write_all(cmd).await
+    }
+
+    pub(crate) async fn transact(&self, cmd: Vec<u8>, res: &mut [u8]) -> Result<()> {
+        let _guard = self.command_lock.lock().await;
+        self.write_mpsse(cmd).await?;
+
+        let res = self.read_all(res).await;
+        if let Err(Error::Timeout { .. }) = res {
+            self.purge_rx().await?;
+        }
+
+        res
+    }
+
+    pub(crate) async fn write_data_bits(
+        &self,
+        bank: gpio::Bank,
+        value: u8,
+        direction: u8,
+    ) -> Result<()> {
+        let opcode = match bank {
+            gpio::Bank::Low => mpsse::SetDataBitsLowByte::byte(),
+            gpio::Bank::High => mpsse::SetDataBitsHighByte::byte(),
+        };
+
+        self.transact(vec![opcode, value, direction], &mut []).await
+    }
+
+    pub async fn exclusive(&self) -> mpsse::Exclusive<'_> {
+        mpsse::Exclusive::new(self, self.command_lock.lock().await)
+    }
+
+    pub async fn read_pins(&self) -> Result<u8> {
+        let pkt = ControlIn {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::ReadPins as u8,
+            value: 0,
+            index: self.channel_index(),
+            length: 1,
+        };
+
+        let res = self.control_in(pkt).await?;
+
+        match res.first() {
+            Some(&pins) => Ok(pins),
+            None => Err(Error::InvalidResponse(res)),
+        }
+    }
+
+    pub async fn status(&self) -> Result<ModemStatus> {
         let pkt = ControlIn {
             control_type: ControlType::Vendor,
             recipient: Recipient::Device,
             request: ControlRequest::GetStatus as u8,
             value: 0,
-            index: self.num as u16 + 1,
+            index: self.channel_index(),
             length: 2,
         };
 
-        let res = self
-            .interface
-            .control_in(pkt, core::time::Duration::from_millis(100))
-            .await?;
-        Ok(())
+        let res = self.control_in(pkt).await?;
+
+        if res.len() < 2 {
+            return Err(Error::InvalidResponse(res));
+        }
+
+        Ok(ModemStatus::from_bytes([res[0], res[1]]))
     }
 
     pub async fn set_dtr(&self) -> Result<()> {
-        todo!();
+        self.set_modem_control(protocol::MODEM_DTR, true).await
     }
 
     pub async fn clear_dtr(&self) -> Result<()> {
-        todo!();
+        self.set_modem_control(protocol::MODEM_DTR, false).await
     }
 
     pub async fn set_rts(&self) -> Result<()> {
-        todo!();
+        self.set_modem_control(protocol::MODEM_RTS, true).await
     }
 
     pub async fn clear_rts(&self) -> Result<()> {
-        todo!();
+        self.set_modem_control(protocol::MODEM_RTS, false).await
+    }
+
+    async fn set_modem_control(&self, line: u16, high: bool) -> Result<()> {
+        let pkt = ControlOut {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::SetModemControl as u8,
+            value: protocol::modem_control(line, high),
+            index: self.channel_index(),
+            data: &[],
+        };
+
+        self.control_out(pkt).await?;
+
+        Ok(())
     }
 
     pub async fn set_event_char(&self, value: char, enable: bool) -> Result<()> {
@@ -268,13 +971,12 @@ impl Interface {
             recipient: Recipient::Device,
             request: ControlRequest::SetEventChar as u8,
             value: u16::from_le_bytes([value as u8, enable as u8]),
-            index: self.num as u16,
+            index: self.channel_index(),
             data: &[],
         };
 
-        self.interface
-            .control_out(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        self.control_out(pkt).await?;
+        *self.event_char.lock().unwrap() = enable.then_some(value as u8);
 
         Ok(())
     }
@@ -285,131 +987,1044 @@ impl Interface {
             recipient: Recipient::Device,
             request: ControlRequest::SetErrorChar as u8,
             value: u16::from_le_bytes([value as u8, enable as u8]),
-            index: self.num as u16,
+            index: self.channel_index(),
             data: &[],
         };
 
-        self.interface
-            .control_out(pkt, core::time::Duration::from_millis(100))
-            .await?;
+        self.control_out(pkt).await?;
 
         Ok(())
     }
 
-    pub async fn read_all(&self, mut buf: &mut [u8]) -> Result<()> {
-        let mut oldbuf = self.read_buffer.lock().await;
-
-        if !oldbuf.0[oldbuf.1..].is_empty() && !buf.is_empty() {
-            let copylen = buf.len().min(oldbuf.0.len().saturating_sub(oldbuf.1));
-            let oldslice = &oldbuf.0[oldbuf.1..oldbuf.1 + copylen];
-            buf[..copylen].clone_from_slice(oldslice);
-            oldbuf.1 += copylen;
-            buf = &mut buf[copylen..];
-        }
+    pub async fn start_background_reader(&self, capacity: usize) -> Result<()> {
+        let mut background_reader = self.background_reader.lock().await;
 
-        if !buf.is_empty() {
-            oldbuf.0 = vec![];
+        if background_reader.is_none() {
+            let mut oldbuf = self.read_buffer.lock().await;
+            let reader = reader::BackgroundReader::spawn(self, capacity, &oldbuf.0[oldbuf.1..]);
+            oldbuf.0.clear();
             oldbuf.1 = 0;
-        }
 
-        while !buf.is_empty() {
-            let mut ep_in = self.ep_in.lock().await;
+            *background_reader = Some(Arc::new(reader));
+        }
 
-            let buffer = ep_in.allocate(self.max_packet_size);
+        Ok(())
+    }
 
-            ep_in.submit(buffer);
+    pub async fn stop_background_reader(&self) -> Result<()> {
+        let reader = self.background_reader.lock().await.take();
 
-            let raw_res = ep_in.next_complete().await;
+        if let Some(reader) = reader {
+            let remaining = reader.stop().await;
 
-            for chunk in raw_res.buffer.chunks(self.max_packet_size) {
-                if chunk.len() > 2 {
-                    let _status = [chunk[0], chunk[1]];
-                    let res = &chunk[2..];
+            let mut oldbuf = self.read_buffer.lock().await;
+            let pos = oldbuf.1;
+            oldbuf.0.drain(..pos);
+            oldbuf.1 = 0;
+            oldbuf.0.extend_from_slice(&remaining);
+        }
 
-                    let buflen = buf.len();
-                    let copylen = res.len().min(buflen);
-                    buf[..copylen].clone_from_slice(&res[0..copylen]);
-                    buf = &mut buf[copylen..];
-                    let res = &res[copylen..];
+        Ok(())
+    }
 
-                    if buf.is_empty() && !res.is_empty() {
-                        oldbuf.0.extend_from_slice(res);
-                    }
-                };
+    pub fn read_queue_depth(&self) -> usize {
+        self.read_queue_depth.load(Ordering::Relaxed)
+    }
+
+    pub fn set_read_queue_depth(&self, depth: usize) -> Result<()> {
+        if depth == 0 {
+            return Err(Error::InvalidParameter(
+                "read queue depth must be non-zero".into(),
+            ));
+        }
+
+        self.read_queue_depth.store(depth, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    pub fn speed(&self) -> Option<nusb::Speed> {
+        self.dev_info.as_ref().and_then(|dev| dev.speed())
+    }
+
+    pub fn read_transfer_size(&self) -> usize {
+        self.max_packet_size * self.read_transfer_packets.load(Ordering::Relaxed)
+    }
+
+    pub fn set_read_transfer_size(&self, bytes: usize) -> Result<()> {
+        if bytes == 0 || bytes % self.max_packet_size != 0 {
+            return Err(Error::InvalidParameter(format!(
+                "read transfer size {} is not a multiple of the {} byte packet size",
+                bytes, self.max_packet_size
+            )));
+        }
+
+        self.set_read_transfer_packets(bytes / self.max_packet_size)
+    }
+
+    pub fn set_read_transfer_packets(&self, packets: usize) -> Result<()> {
+        if packets == 0 {
+            return Err(Error::InvalidParameter(
+                "read transfer size must be at least one packet".into(),
+            ));
+        }
+
+        self.read_transfer_packets.store(packets, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    pub async fn set_performance_profile(&self, profile: PerformanceProfile) -> Result<()> {
+        let (latency_ms, packets, depth) = match profile {
+            PerformanceProfile::Latency => (1, 1, DEFAULT_READ_QUEUE_DEPTH),
+            PerformanceProfile::Balanced => (16, 8, DEFAULT_READ_QUEUE_DEPTH),
+            PerformanceProfile::Throughput => (16, 64, 8),
+        };
+
+        self.set_latency_timer(Duration::from_millis(latency_ms))
+            .await?;
+        self.set_event_char('\0', false).await?;
+        self.set_read_transfer_packets(packets)?;
+        self.set_read_queue_depth(depth)?;
+
+        Ok(())
+    }
+
+    pub fn read_write_timeouts(&self) -> (Option<Duration>, Option<Duration>) {
+        *self.timeouts.lock().unwrap()
+    }
+
+    pub fn set_read_write_timeouts(&self, read: Option<Duration>, write: Option<Duration>) {
+        *self.timeouts.lock().unwrap() = (read, write);
+    }
+
+    pub fn config(&self) -> Config {
+        *self.config.lock().unwrap()
+    }
+
+    pub fn set_config(&self, config: Config) {
+        *self.config.lock().unwrap() = config;
+        self.set_read_write_timeouts(config.io_timeout, config.io_timeout);
+    }
+
+    pub async fn interface_config(&self) -> Result<InterfaceConfig> {
+        let (read_timeout, write_timeout) = self.read_write_timeouts();
+
+        Ok(InterfaceConfig {
+            baudrate: *self.baudrate.lock().unwrap(),
+            flow_control: self
+                .flow_control
+                .lock()
+                .unwrap()
+                .map(|(flow_control, _)| flow_control),
+            line_settings: self.line_settings.lock().await.0,
+            latency_timer: self.latency_timer().await?,
+            read_timeout,
+            write_timeout,
+            read_queue_depth: self.read_queue_depth(),
+            read_transfer_packets: self.read_transfer_packets.load(Ordering::Relaxed),
+            config: self.config(),
+        })
+    }
+
+    pub async fn apply_interface_config(&self, config: &InterfaceConfig) -> Result<()> {
+        self.set_config(config.config);
+        if let Some(baudrate) = config.baudrate {
+            self.set_baudrate(baudrate).await?;
+        }
+        if let Some(flow_control) = config.flow_control {
+            self.set_flow_control(flow_control).await?;
+        }
+        self.set_line_settings(config.line_settings).await?;
+        self.set_latency_timer(config.latency_timer).await?;
+        self.set_read_write_timeouts(config.read_timeout, config.write_timeout);
+        self.set_read_queue_depth(config.read_queue_depth)?;
+        self.set_read_transfer_packets(config.read_transfer_packets)?;
+
+        Ok(())
+    }
+
+    async fn control_out(&self, pkt: ControlOut<'_>) -> Result<()> {
+        self.connection.ensure_connected()?;
+
+        let config = self.config();
+        let mut attempt = 0;
+
+        loop {
+            let attempt_pkt = ControlOut {
+                control_type: pkt.control_type,
+                recipient: pkt.recipient,
+                request: pkt.request,
+                value: pkt.value,
+                index: pkt.index,
+                data: pkt.data,
+            };
+
+            #[cfg(feature = "trace")]
+            let started = std::time::Instant::now();
+
+            let res = self
+                .transport
+                .control_out(attempt_pkt, config.control_timeout)
+                .await;
+
+            #[cfg(feature = "trace")]
+            trace::control_out(self.num, &pkt, started.elapsed(), &res);
+            self.counters.record_control(&res);
+            self.connection.observe(&res);
+
+            match res {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    retry_control(pkt.request, err, attempt, config.retries).await?;
+                    self.counters.record_retransmit();
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn control_in(&self, pkt: ControlIn) -> Result<Vec<u8>> {
+        self.connection.ensure_connected()?;
+
+        let config = self.config();
+        let mut attempt = 0;
+
+        loop {
+            let attempt_pkt = ControlIn {
+                control_type: pkt.control_type,
+                recipient: pkt.recipient,
+                request: pkt.request,
+                value: pkt.value,
+                index: pkt.index,
+                length: pkt.length,
+            };
+
+            #[cfg(feature = "trace")]
+            let started = std::time::Instant::now();
+
+            let res = self
+                .transport
+                .control_in(attempt_pkt, config.control_timeout)
+                .await;
+
+            #[cfg(feature = "trace")]
+            trace::control_in(self.num, &pkt, started.elapsed(), &res);
+            self.counters.record_control(&res);
+            self.connection.observe(&res);
+
+            match res {
+                Ok(data) => return Ok(data),
+                Err(err) => {
+                    retry_control(pkt.request, err, attempt, config.retries).await?;
+                    self.counters.record_retransmit();
+                    attempt += 1;
+                }
             }
         }
+    }
+
+    fn record_read(&self, completion: &Completion) {
+        self.connection.observe(&completion.status);
+        self.counters.record_read(
+            &completion.status,
+            &completion.buffer,
+            self.read_transfer_size(),
+            self.packet_decoder(),
+        );
+    }
+
+    pub fn packet_decoder(&self) -> FtdiPacketDecoder {
+        FtdiPacketDecoder::new(self.max_packet_size)
+    }
+
+    fn fill_read_queue(&self, ep_in: &mut dyn BulkEndpoint) {
+        while ep_in.pending() < self.read_queue_depth() {
+            let buffer = self.read_pool.take(ep_in, self.read_transfer_size());
+            ep_in.submit(buffer);
+        }
+    }
+
+    async fn background_reader(&self) -> Option<Arc<reader::BackgroundReader>> {
+        self.background_reader.lock().await.clone()
+    }
+
+    pub fn line_events(&self) -> impl futures_core::Stream<Item = LineEvent> {
+        let monitor = self.line_monitor.clone();
+
+        futures_lite::stream::unfold(monitor, |monitor| async move {
+            let event = monitor.next().await;
+            Some((event, monitor))
+        })
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.counters.snapshot()
+    }
+
+    pub async fn bytes_buffered(&self) -> usize {
+        let local = {
+            let oldbuf = self.read_buffer.lock().await;
+            oldbuf.0.len() - oldbuf.1
+        };
+
+        match self.background_reader().await {
+            Some(reader) => local + reader.buffered(),
+            None => local,
+        }
+    }
+
+    pub async fn peek(&self, buf: &mut [u8]) -> usize {
+        let mut n = {
+            let oldbuf = self.read_buffer.lock().await;
+            let data = &oldbuf.0[oldbuf.1..];
+            let n = data.len().min(buf.len());
+            buf[..n].copy_from_slice(&data[..n]);
+            n
+        };
+
+        if let Some(reader) = self.background_reader().await {
+            n += reader.peek(&mut buf[n..]);
+        }
+
+        n
+    }
+
+    pub async fn discard_input_buffer(&self) {
+        {
+            let mut oldbuf = self.read_buffer.lock().await;
+            oldbuf.0.clear();
+            oldbuf.1 = 0;
+        }
+
+        if let Some(reader) = self.background_reader().await {
+            reader.clear();
+        }
+    }
+
+    pub async fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        match self.read_write_timeouts().0 {
+            Some(timeout) => self.read_timeout(buf, timeout).await,
+            None => self.read_available(buf).await,
+        }
+    }
+
+    async fn read_available(&self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let data = self.read_some(buf.len()).await?;
+        buf[..data.len()].copy_from_slice(&data);
+
+        Ok(data.len())
+    }
+
+    pub async fn read_timeout(&self, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        match with_timeout(timeout, self.read_available(buf)).await {
+            Some(res) => res,
+            None => Ok(0),
+        }
+    }
+
+    pub async fn read_exact_timeout(&self, mut buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut total = 0;
+
+        while !buf.is_empty() {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match with_timeout(remaining, self.read_available(buf)).await {
+                Some(res) => {
+                    let len = res?;
+                    total += len;
+                    buf = &mut buf[len..];
+                }
+                None => break,
+            }
+        }
+
+        Ok(total)
+    }
+
+    pub async fn read_until_event(&self, event: u8) -> Result<Vec<u8>> {
+        if *self.event_char.lock().unwrap() != Some(event) {
+            self.set_event_char(event as char, true).await?;
+        }
+
+        let mut frame = Vec::new();
+
+        loop {
+            let data = self.read_some(self.max_packet_size).await?;
+
+            match data.iter().position(|&byte| byte == event) {
+                Some(pos) => {
+                    frame.extend_from_slice(&data[..=pos]);
+                    self.unread(&data[pos + 1..]).await;
+
+                    return Ok(frame);
+                }
+                None => frame.extend_from_slice(&data),
+            }
+        }
+    }
+
+    async fn unread(&self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        if let Some(reader) = self.background_reader().await {
+            reader.unread(data);
+            return;
+        }
+
+        let mut oldbuf = self.read_buffer.lock().await;
+        let mut buf = data.to_vec();
+        buf.extend_from_slice(&oldbuf.0[oldbuf.1..]);
+        *oldbuf = (buf, 0);
+    }
+
+    pub(crate) async fn read_some(&self, max: usize) -> Result<Vec<u8>> {
+        self.connection.ensure_connected()?;
+
+        if let Some(reader) = self.background_reader().await {
+            let mut data = vec![0u8; max];
+            let len = reader.read(&mut data).await?;
+            data.truncate(len);
+
+            return Ok(data);
+        }
+
+        let mut oldbuf = self.read_buffer.lock().await;
+
+        loop {
+            if oldbuf.1 < oldbuf.0.len() {
+                let end = oldbuf.0.len().min(oldbuf.1 + max);
+                let data = oldbuf.0[oldbuf.1..end].to_vec();
+                oldbuf.1 = end;
+                return Ok(data);
+            }
+
+            oldbuf.0.clear();
+            oldbuf.1 = 0;
+
+            let mut ep_in = self.ep_in.lock().await;
+
+            self.fill_read_queue(&mut **ep_in);
+
+            let completion = ep_in.next_complete().await;
+            self.record_read(&completion);
+            completion.status?;
+
+            #[cfg(feature = "trace")]
+            trace::bulk_in(ep_in.endpoint_address(), &completion.buffer);
+
+            let decoder = self.packet_decoder();
+            self.line_monitor
+                .record_packets(&completion.buffer, decoder);
+            decoder.decode_into(&completion.buffer, &mut oldbuf.0);
+            self.read_pool
+                .put(completion.buffer, self.read_queue_depth());
+        }
+    }
+
+    pub async fn read_all(&self, buf: &mut [u8]) -> Result<()> {
+        self.connection.ensure_connected()?;
+
+        let Some(timeout) = self.read_write_timeouts().0 else {
+            return self.read_packets(buf).await;
+        };
+
+        let requested = buf.len();
+        match with_timeout(timeout, self.read_packets(buf)).await {
+            Some(res) => res,
+            None => Err(Error::Timeout {
+                operation: "read",
+                requested,
+                completed: self.bytes_buffered().await.min(requested),
+            }),
+        }
+    }
+
+    async fn read_packets(&self, mut buf: &mut [u8]) -> Result<()> {
+        if let Some(reader) = self.background_reader().await {
+            while !buf.is_empty() {
+                let len = reader.read(buf).await?;
+                buf = &mut buf[len..];
+            }
+
+            return Ok(());
+        }
+
+        let mut oldbuf = self.read_buffer.lock().await;
+
+        if oldbuf.0.len() - oldbuf.1 < buf.len() {
+            let consumed = oldbuf.1;
+            oldbuf.0.drain(..consumed);
+            oldbuf.1 = 0;
+        }
+
+        while oldbuf.0.len() - oldbuf.1 < buf.len() {
+            let mut ep_in = self.ep_in.lock().await;
+
+            self.fill_read_queue(&mut **ep_in);
+
+            let raw_res = ep_in.next_complete().await;
+            self.record_read(&raw_res);
+
+            #[cfg(feature = "trace")]
+            trace::bulk_in(ep_in.endpoint_address(), &raw_res.buffer);
+
+            self.park_packets(&mut oldbuf.0, &raw_res.buffer);
+            self.read_pool.put(raw_res.buffer, self.read_queue_depth());
+            raw_res.status?;
+        }
+
+        let start = oldbuf.1;
+        buf.copy_from_slice(&oldbuf.0[start..start + buf.len()]);
+        oldbuf.1 += buf.len();
+
+        Ok(())
+    }
+
+    fn park_packets(&self, parked: &mut Vec<u8>, buffer: &[u8]) {
+        for packet in self.packet_decoder().packets(buffer) {
+            self.line_monitor.record(packet.status);
+            parked.extend_from_slice(packet.data);
+        }
+    }
+
+    pub async fn abort_pending_io(&self) -> Result<()> {
+        {
+            let mut ep_out = self.ep_out.lock().await;
+            ep_out.cancel_all();
+            while ep_out.pending() > 0 {
+                let completion = ep_out.next_complete().await;
+                self.counters
+                    .record_write(&completion.status, completion.actual_len);
+            }
+        }
+
+        if self.background_reader().await.is_some() {
+            return Ok(());
+        }
+
+        let mut oldbuf = self.read_buffer.lock().await;
+        let mut ep_in = self.ep_in.lock().await;
+
+        ep_in.cancel_all();
+        while ep_in.pending() > 0 {
+            let completion = ep_in.next_complete().await;
+            self.record_read(&completion);
+            self.park_packets(&mut oldbuf.0, &completion.buffer);
+            self.read_pool
+                .put(completion.buffer, self.read_queue_depth());
+        }
 
         Ok(())
     }
 
     pub async fn write_all(&self, buf: Vec<u8>) -> Result<()> {
+        self.write_vectored(&[IoSlice::new(&buf)]).await
+    }
+
+    pub async fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> Result<()> {
+        self.connection.ensure_connected()?;
+
+        let mut written = 0;
+        let Some(timeout) = self.read_write_timeouts().1 else {
+            return self.write_chunks(bufs, &mut written).await;
+        };
+
+        match with_timeout(timeout, self.write_chunks(bufs, &mut written)).await {
+            Some(res) => res,
+            None => {
+                let mut ep_out = self.ep_out.lock().await;
+                ep_out.cancel_all();
+                while ep_out.pending() > 0 {
+                    ep_out.next_complete().await;
+                }
+
+                Err(Error::Timeout {
+                    operation: "write",
+                    requested: bufs.iter().map(|buf| buf.len()).sum(),
+                    completed: written,
+                })
+            }
+        }
+    }
+
+    pub fn with_timeouts(self, read: Duration, write: Duration) -> Self {
+        self.set_read_write_timeouts(Some(read), Some(write));
+        self
+    }
+
+    async fn write_chunks(&self, bufs: &[IoSlice<'_>], written: &mut usize) -> Result<()> {
         let mut ep_out = self.ep_out.lock().await;
 
-        ep_out.submit(buf.into());
+        let chunk_size = self.max_packet_size * WRITE_CHUNK_PACKETS;
+        let mut slices = bufs
+            .iter()
+            .map(|buf| &buf[..])
+            .filter(|buf| !buf.is_empty());
+        let mut current = slices.next();
 
-        ep_out.next_complete().await.status?;
+        while current.is_some() {
+            if ep_out.pending() >= WRITE_QUEUE_DEPTH {
+                self.complete_write(&mut **ep_out, written).await?;
+            }
+
+            let mut chunk = ep_out.allocate(chunk_size);
+            while let Some(data) = current {
+                let len = data.len().min(chunk_size - chunk.len());
+                chunk.extend_from_slice(&data[..len]);
+
+                current = match &data[len..] {
+                    [] => slices.next(),
+                    rest => Some(rest),
+                };
+                if chunk.len() == chunk_size {
+                    break;
+                }
+            }
+
+            #[cfg(feature = "trace")]
+            trace::bulk_out(ep_out.endpoint_address(), &chunk);
+
+            ep_out.submit(chunk);
+        }
+
+        while ep_out.pending() > 0 {
+            self.complete_write(&mut **ep_out, written).await?;
+        }
 
         Ok(())
     }
 
-    fn in_endpoint(&self) -> u8 {
-        (((self.num + 1) * 2) - 1) | 0x80
+    async fn complete_write(
+        &self,
+        ep_out: &mut dyn BulkEndpoint,
+        written: &mut usize,
+    ) -> Result<()> {
+        let completion = ep_out.next_complete().await;
+        self.counters
+            .record_write(&completion.status, completion.actual_len);
+        self.connection.observe(&completion.status);
+
+        match completion.status {
+            Ok(()) => {
+                *written += completion.actual_len;
+                Ok(())
+            }
+            Err(source) => {
+                ep_out.cancel_all();
+                while ep_out.pending() > 0 {
+                    ep_out.next_complete().await;
+                }
+
+                match source {
+                    TransferError::Disconnected => Err(Error::Disconnected),
+                    source => Err(Error::WriteFailed {
+                        written: *written,
+                        source,
+                    }),
+                }
+            }
+        }
     }
 
-    fn out_endpoint(&self) -> u8 {
-        (self.num + 1) * 2
+    pub async fn eeprom_read_word(&self, addr: u16) -> Result<u16> {
+        let pkt = ControlIn {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::ReadEeprom as u8,
+            value: 0,
+            index: addr,
+            length: 2,
+        };
+
+        let res = self.control_in(pkt).await?;
+
+        if res.len() < 2 {
+            return Err(Error::InvalidResponse(res));
+        }
+
+        Ok(u16::from_le_bytes([res[0], res[1]]))
+    }
+
+    pub async fn eeprom_write_word(&self, addr: u16, value: u16) -> Result<()> {
+        let pkt = ControlOut {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::WriteEeprom as u8,
+            value,
+            index: addr,
+            data: &[],
+        };
+
+        self.control_out(pkt).await?;
+
+        Ok(())
+    }
+
+    pub async fn eeprom_erase(&self) -> Result<()> {
+        if self.device_type == DeviceType::FtX {
+            return Err(Error::InvalidParameter(
+                "FT-X MTP memory cannot be erased".into(),
+            ));
+        }
+
+        let pkt = ControlOut {
+            control_type: ControlType::Vendor,
+            recipient: Recipient::Device,
+            request: ControlRequest::EraseEeprom as u8,
+            value: 0,
+            index: 0,
+            data: &[],
+        };
+
+        self.control_out(pkt).await?;
+
+        Ok(())
+    }
+
+    pub async fn eeprom_read_all(&self) -> Result<Vec<u16>> {
+        let mut words = Vec::with_capacity(self.device_type.eeprom_words());
+
+        for addr in 0..self.device_type.eeprom_words() {
+            words.push(self.eeprom_read_word(addr as u16).await?);
+        }
+
+        Ok(words)
+    }
+
+    pub async fn eeprom_write_all(&self, words: &[u16]) -> Result<()> {
+        if words.len() > self.device_type.eeprom_words() {
+            return Err(Error::InvalidParameter(format!(
+                "eeprom image of {} words exceeds the {} word eeprom",
+                words.len(),
+                self.device_type.eeprom_words()
+            )));
+        }
+
+        for (addr, &word) in words.iter().enumerate() {
+            self.eeprom_write_word(addr as u16, word).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn chip_id(&self) -> Result<u32> {
+        if self.device_type != DeviceType::FT232R {
+            return Err(Error::InvalidParameter(format!(
+                "FTDIChip-ID is not available on {:?}",
+                self.device_type
+            )));
+        }
+
+        let hi = self.eeprom_read_word(0x43).await?.swap_bytes() as u32;
+        let lo = self.eeprom_read_word(0x44).await?.swap_bytes() as u32;
+        let id = (hi << 16 | lo).to_le_bytes().map(chip_id_shift);
+
+        Ok(u32::from_le_bytes(id) ^ 0xa5f0f7d1)
+    }
+
+    pub async fn read_eeprom(&self) -> Result<eeprom::Eeprom> {
+        let words = self.eeprom_read_all().await?;
+
+        eeprom::Eeprom::parse(self.device_type, &words)
+    }
+
+    pub async fn flash_eeprom(&self, eeprom: &eeprom::Eeprom) -> Result<()> {
+        if eeprom.device_type != self.device_type {
+            return Err(Error::InvalidParameter(format!(
+                "eeprom layout for {:?} cannot be flashed to {:?}",
+                eeprom.device_type, self.device_type
+            )));
+        }
+
+        let words = eeprom.to_words()?;
+        self.eeprom_write_all(&words).await?;
+
+        let readback = self.eeprom_read_all().await?;
+        for (addr, (&expected, &actual)) in words.iter().zip(readback.iter()).enumerate() {
+            if expected != actual {
+                return Err(Error::EepromVerify {
+                    addr: addr as u16,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn channel_index(&self) -> u16 {
+        protocol::channel_index(self.num)
+    }
+}
+
+async fn retry_control(request: u8, err: TransferError, attempt: u32, retries: u32) -> Result<()> {
+    if !matches!(err, TransferError::Stall | TransferError::Cancelled) {
+        return Err(err.into());
+    }
+
+    if attempt >= retries {
+        return Err(Error::ControlFailed {
+            request,
+            attempts: attempt + 1,
+            source: err,
+        });
+    }
+
+    futures_timer::Delay::new(CONTROL_RETRY_BACKOFF * 2u32.pow(attempt)).await;
+
+    Ok(())
+}
+
+async fn with_timeout<F: Future>(timeout: Duration, fut: F) -> Option<F::Output> {
+    futures_lite::future::or(async { Some(fut.await) }, async {
+        futures_timer::Delay::new(timeout).await;
+        None
+    })
+    .await
+}
+
+fn chip_id_shift(value: u8) -> u8 {
+    ((value & 0x01) << 1)
+        | ((value & 0x02) << 5)
+        | ((value & 0x04) >> 2)
+        | ((value & 0x08) << 4)
+        | ((value & 0x10) >> 1)
+        | ((value & 0x20) >> 1)
+        | ((value & 0x40) >> 4)
+        | ((value & 0x80) >> 2)
+}
+
+const BAUDRATE_FRAC_CODE: [u32; 8] = [0, 3, 2, 4, 1, 5, 6, 7];
+
+fn baudrate_clkbits(baudrate: u32, clk: u32, clk_div: u32) -> (u32, u32) {
+    if baudrate >= clk / clk_div {
+        (0, clk / clk_div)
+    } else if baudrate >= clk / (clk_div + clk_div / 2) {
+        (1, clk / (clk_div + clk_div / 2))
+    } else if baudrate >= clk / (2 * clk_div) {
+        (2, clk / (2 * clk_div))
+    } else {
+        let divisor = (clk * 16 / clk_div / baudrate + 1) / 2;
+        let divisor = if divisor > 0x20000 { 0x1ffff } else { divisor };
+        let actual = (clk * 16 / clk_div / divisor + 1) / 2;
+        let encoded = (divisor >> 3) | (BAUDRATE_FRAC_CODE[(divisor & 7) as usize] << 14);
+
+        (encoded, actual)
     }
 }
 
 impl InterfaceInfo {
     pub async fn open(&mut self) -> Result<Interface> {
         let dev = self.dev.open().await?;
+        self.claim(dev).await
+    }
 
-        let max_packet_size = dev
-            .active_configuration()?
-            .interface_alt_settings()
-            .last()
-            .unwrap()
-            .endpoints()
-            .last()
-            .unwrap()
-            .max_packet_size();
-
-        let interface = dev.detach_and_claim_interface(self.num).await?;
+    pub async fn open_with(&mut self, options: &OpenOptions) -> Result<Interface> {
+        let interface = self.open().await?;
+        options.apply(&interface).await?;
+        Ok(interface)
+    }
 
-        let ep_in = Arc::new(Mutex::new(
-            interface.endpoint::<Bulk, In>((((self.num + 1) * 2) - 1) | 0x80)?,
-        ));
-        let ep_out = Arc::new(Mutex::new(
-            interface.endpoint::<Bulk, Out>((self.num + 1) * 2)?,
+    async fn claim(&self, dev: nusb::Device) -> Result<Interface> {
+        let transport = Arc::new(UsbTransport::new(
+            dev.detach_and_claim_interface(self.num).await?,
         ));
 
-        let interface = Interface {
-            dev,
-            dev_info: self.dev.clone(),
-            device_type: self.device_type,
-            interface,
-            num: self.num,
-            max_packet_size,
-            read_buffer: Arc::default(),
+        let ep_in = transport.bulk_in(self.num)?;
+        let max_packet_size = match self.dev.speed() {
+            Some(nusb::Speed::Low | nusb::Speed::Full) => FULL_SPEED_PACKET_SIZE,
+            Some(_) => HIGH_SPEED_PACKET_SIZE,
+            None => ep_in.max_packet_size(),
+        };
+        let ep_out = transport.bulk_out(self.num)?;
+
+        let mut interface = Interface::from_parts(
+            transport,
             ep_in,
             ep_out,
-        };
+            self.device_type,
+            self.num,
+            max_packet_size,
+        );
+        interface.dev = Some(dev);
+        interface.dev_info = Some(self.dev.clone());
 
         Ok(interface)
     }
     //
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, Default)]
+pub struct OpenOptions {
+    interface_config: Option<InterfaceConfig>,
+    bitmode: Option<(u8, Bitmode)>,
+    baudrate: Option<u32>,
+    flow_control: Option<FlowControl>,
+    latency_timer: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    buffer_size: Option<usize>,
+    read_transfer_size: Option<usize>,
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn interface_config(mut self, config: InterfaceConfig) -> Self {
+        self.interface_config = Some(config);
+        self
+    }
+
+    pub fn bitmode(mut self, bitmask: u8, bitmode: Bitmode) -> Self {
+        self.bitmode = Some((bitmask, bitmode));
+        self
+    }
+
+    pub fn baudrate(mut self, baudrate: u32) -> Self {
+        self.baudrate = Some(baudrate);
+        self
+    }
+
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = Some(flow_control);
+        self
+    }
+
+    pub fn latency_timer(mut self, timer: Duration) -> Self {
+        self.latency_timer = Some(timer);
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    pub fn buffer_size(mut self, capacity: usize) -> Self {
+        self.buffer_size = Some(capacity);
+        self
+    }
+
+    pub fn read_transfer_size(mut self, bytes: usize) -> Self {
+        self.read_transfer_size = Some(bytes);
+        self
+    }
+
+    pub async fn open(&self, info: &mut InterfaceInfo) -> Result<Interface> {
+        info.open_with(self).await
+    }
+
+    async fn apply(&self, interface: &Interface) -> Result<()> {
+        if let Some(config) = &self.interface_config {
+            interface.apply_interface_config(config).await?;
+        }
+
+        if let Some((bitmask, bitmode)) = self.bitmode {
+            interface.set_bitmode(bitmask, bitmode).await?;
+        }
+
+        if let Some(baudrate) = self.baudrate {
+            interface.set_baudrate(baudrate).await?;
+        }
+
+        if let Some(flow_control) = self.flow_control {
+            interface.set_flow_control(flow_control).await?;
+        }
+
+        if let Some(timer) = self.latency_timer {
+            interface.set_latency_timer(timer).await?;
+        }
+
+        let (read_timeout, write_timeout) = match &self.interface_config {
+            Some(config) => (
+                self.read_timeout.or(config.read_timeout),
+                self.write_timeout.or(config.write_timeout),
+            ),
+            None => (self.read_timeout, self.write_timeout),
+        };
+        interface.set_read_write_timeouts(read_timeout, write_timeout);
+
+        if let Some(bytes) = self.read_transfer_size {
+            interface.set_read_transfer_size(bytes)?;
+        }
+
+        if let Some(capacity) = self.buffer_size {
+            interface.start_background_reader(capacity).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DeviceType {
     FT4232H,
     FT2232C,
     FT2232H,
     FT232H,
+    FT232R,
+    FtX,
+    Unknown(u16),
     // FT232H = 0x6014
 }
 
+impl DeviceType {
+    pub fn from_ids(product_id: u16, device_version: u16) -> Self {
+        match (product_id, device_version) {
+            (0x6001, 0x600) => DeviceType::FT232R,
+            (0x6010, 0x500) => DeviceType::FT2232C,
+            (0x6010, 0x700) => DeviceType::FT2232H,
+            (0x6011, 0x800) => DeviceType::FT4232H,
+            (0x6014, 0x900) => DeviceType::FT232H,
+            (0x6015, 0x1000) => DeviceType::FtX,
+            (0x6001 | 0x6010 | 0x6011 | 0x6014 | 0x6015, version) => DeviceType::Unknown(version),
+            (_, 0x500) => DeviceType::FT2232C,
+            (_, 0x600) => DeviceType::FT232R,
+            (_, 0x700) => DeviceType::FT2232H,
+            (_, 0x800) => DeviceType::FT4232H,
+            (_, 0x900) => DeviceType::FT232H,
+            (_, 0x1000) => DeviceType::FtX,
+            (_, version) => DeviceType::Unknown(version),
+        }
+    }
+
+    pub fn interface_kind(&self, num: u8) -> Option<InterfaceType> {
+        match (self, num) {
+            (DeviceType::FT2232C, 0) => Some(InterfaceType::Mpsse),
+            (DeviceType::FT2232C, 1) => Some(InterfaceType::Uart),
+            (DeviceType::FT2232H, 0..=1) => Some(InterfaceType::Mpsse),
+            (DeviceType::FT4232H, 0..=1) => Some(InterfaceType::Mpsse),
+            (DeviceType::FT4232H, 2..=3) => Some(InterfaceType::Uart),
+            (DeviceType::FT232H, 0) => Some(InterfaceType::Mpsse),
+            (DeviceType::FT232R | DeviceType::FtX, 0) => Some(InterfaceType::Uart),
+            _ => None,
+        }
+    }
+
+    pub fn eeprom_words(&self) -> usize {
+        match self {
+            DeviceType::FT2232C | DeviceType::FT232R | DeviceType::Unknown(_) => 64,
+            DeviceType::FT2232H | DeviceType::FT4232H | DeviceType::FT232H | DeviceType::FtX => 128,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DeviceInfo {
     pub dev: nusb::DeviceInfo,
@@ -417,67 +2032,262 @@ pub struct DeviceInfo {
     pub interfaces: Vec<InterfaceInfo>,
 }
 
-pub async fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>> {
-    let devs = nusb::list_devices().await?;
-    let devs = devs.filter(|dev| dev.vendor_id() == 0x0403);
+impl DeviceInfo {
+    pub fn port(&self, port: PortLetter) -> Option<&InterfaceInfo> {
+        self.interfaces.iter().find(|i| i.num == port.index())
+    }
 
-    let devs = devs.map(|dev| {
-        let version = dev.device_version();
+    pub async fn open_port(&self, port: PortLetter) -> Result<Interface> {
+        self.port(port)
+            .cloned()
+            .ok_or(Error::DeviceNotFound)?
+            .open()
+            .await
+    }
 
-        match version {
-            0x800 => DeviceInfo {
-                dev: dev.clone(),
-                device_type: DeviceType::FT4232H,
-                interfaces: dev
-                    .interfaces()
-                    .enumerate()
-                    .map(|(i, info)| match i {
-                        0..=1 => InterfaceInfo {
-                            num: i as u8,
-                            dev: dev.clone(),
-                            device_type: DeviceType::FT4232H,
-                            kind: InterfaceType::Mpsse,
-                        },
-                        2..=3 => InterfaceInfo {
-                            num: i as u8,
-                            dev: dev.clone(),
-                            device_type: DeviceType::FT4232H,
-                            kind: InterfaceType::Uart,
-                        },
-                        _ => panic!("unknown interface"),
-                    })
-                    .collect(),
-            },
-            0x700 | 0x900 => DeviceInfo {
-                dev: dev.clone(),
-                device_type: DeviceType::FT232H,
-                interfaces: vec![InterfaceInfo {
-                    num: 0,
-                    dev: dev.clone(),
-                    device_type: DeviceType::FT232H,
-                    kind: InterfaceType::Mpsse,
-                }],
-            },
-            0x600 => DeviceInfo {
-                dev: dev.clone(),
-                device_type: DeviceType::FT232H,
-                interfaces: vec![InterfaceInfo {
-                    num: 0,
-                    dev: dev.clone(),
-                    device_type: DeviceType::FT232H,
-                    kind: InterfaceType::Uart,
-                }],
-            },
+    pub async fn open_all(&self) -> Result<Vec<(PortLetter, Interface)>> {
+        let dev = self.dev.open().await?;
+
+        let mut ports = Vec::with_capacity(self.interfaces.len());
+        for info in &self.interfaces {
+            let port = PortLetter::try_from(info.num)?;
+            ports.push((port, info.claim(dev.clone()).await?));
+        }
+
+        Ok(ports)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortLetter {
+    A,
+    B,
+    C,
+    D,
+}
+
+impl PortLetter {
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for PortLetter {
+    type Error = Error;
+
+    fn try_from(index: u8) -> Result<Self> {
+        match index {
+            0 => Ok(PortLetter::A),
+            1 => Ok(PortLetter::B),
+            2 => Ok(PortLetter::C),
+            3 => Ok(PortLetter::D),
+            _ => Err(Error::InvalidParameter(format!(
+                "invalid port index {}",
+                index
+            ))),
+        }
+    }
+}
+
+impl TryFrom<char> for PortLetter {
+    type Error = Error;
 
-            n => panic!("unknown device version {:x?}", n),
+    fn try_from(letter: char) -> Result<Self> {
+        match letter.to_ascii_uppercase() {
+            upper @ 'A'..='D' => PortLetter::try_from(upper as u8 - b'A'),
+            _ => Err(Error::InvalidParameter(format!(
+                "invalid port letter {:?}",
+                letter
+            ))),
         }
-    });
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DeviceFilter {
+    ids: Vec<(u16, u16)>,
+    serial_number: Option<String>,
+    description: Option<String>,
+    port_path: Option<(String, Vec<u8>)>,
+}
+
+impl DeviceFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_id(mut self, vendor_id: u16, product_id: u16) -> Self {
+        self.ids.push((vendor_id, product_id));
+        self
+    }
+
+    pub fn serial_number(mut self, serial_number: &str) -> Self {
+        self.serial_number = Some(serial_number.into());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn port_path(mut self, bus_id: &str, ports: &[u8]) -> Self {
+        self.port_path = Some((bus_id.into(), ports.to_vec()));
+        self
+    }
+
+    pub fn matches(&self, dev: &nusb::DeviceInfo) -> bool {
+        if dev.vendor_id() != 0x0403 && !self.ids.contains(&(dev.vendor_id(), dev.product_id())) {
+            return false;
+        }
+
+        if let Some(sn) = &self.serial_number {
+            if dev.serial_number() != Some(sn.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(description) = &self.description {
+            if dev.product_string() != Some(description.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some((bus_id, ports)) = &self.port_path {
+            if dev.bus_id() != bus_id || dev.port_chain() != ports.as_slice() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn device_info(dev: nusb::DeviceInfo) -> DeviceInfo {
+    let device_type = DeviceType::from_ids(dev.product_id(), dev.device_version());
+    let interfaces = dev
+        .interfaces()
+        .enumerate()
+        .filter_map(|(i, _)| {
+            Some(InterfaceInfo {
+                num: i as u8,
+                dev: dev.clone(),
+                device_type,
+                kind: device_type.interface_kind(i as u8)?,
+            })
+        })
+        .collect();
+
+    DeviceInfo {
+        dev,
+        device_type,
+        interfaces,
+    }
+}
+
+pub async fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>> {
+    list_devices_with_filter(DeviceFilter::default()).await
+}
+
+pub async fn list_devices_with_filter(
+    filter: DeviceFilter,
+) -> Result<impl Iterator<Item = DeviceInfo>> {
+    let devs = nusb::list_devices().await?;
+    let devs = devs.filter(move |dev| filter.matches(dev));
+
+    let devs = devs.map(device_info);
 
     Ok(devs)
 }
 
+pub async fn list_devices_strict() -> Result<Vec<DeviceInfo>> {
+    list_devices()
+        .await?
+        .map(|dev| match dev.device_type {
+            DeviceType::Unknown(version) => Err(Error::UnknownDevice { version }),
+            _ => Ok(dev),
+        })
+        .collect()
+}
+
 pub async fn list_interfaces() -> Result<impl Iterator<Item = InterfaceInfo>> {
     let devs = list_devices().await?;
     let devs = devs.flat_map(|dev| dev.interfaces);
     Ok(devs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_type_from_ftdi_ids() {
+        for (product_id, device_version, device_type) in [
+            (0x6001, 0x600, DeviceType::FT232R),
+            (0x6010, 0x500, DeviceType::FT2232C),
+            (0x6010, 0x700, DeviceType::FT2232H),
+            (0x6011, 0x800, DeviceType::FT4232H),
+            (0x6014, 0x900, DeviceType::FT232H),
+            (0x6015, 0x1000, DeviceType::FtX),
+        ] {
+            assert_eq!(
+                DeviceType::from_ids(product_id, device_version),
+                device_type
+            );
+        }
+    }
+
+    #[test]
+    fn mismatched_ftdi_ids_are_unknown() {
+        assert_eq!(
+            DeviceType::from_ids(0x6014, 0x700),
+            DeviceType::Unknown(0x700)
+        );
+        assert_eq!(
+            DeviceType::from_ids(0x6010, 0x900),
+            DeviceType::Unknown(0x900)
+        );
+        assert_eq!(
+            DeviceType::from_ids(0x6001, 0x400),
+            DeviceType::Unknown(0x400)
+        );
+    }
+
+    #[test]
+    fn device_type_from_clone_ids() {
+        for (device_version, device_type) in [
+            (0x500, DeviceType::FT2232C),
+            (0x600, DeviceType::FT232R),
+            (0x700, DeviceType::FT2232H),
+            (0x800, DeviceType::FT4232H),
+            (0x900, DeviceType::FT232H),
+            (0x1000, DeviceType::FtX),
+            (0x200, DeviceType::Unknown(0x200)),
+        ] {
+            assert_eq!(DeviceType::from_ids(0x8a98, device_version), device_type);
+        }
+    }
+
+    #[test]
+    fn interface_kinds() {
+        use InterfaceType::{Mpsse, Uart};
+
+        for (device_type, kinds) in [
+            (DeviceType::FT2232C, &[Some(Mpsse), Some(Uart), None][..]),
+            (DeviceType::FT2232H, &[Some(Mpsse), Some(Mpsse), None]),
+            (
+                DeviceType::FT4232H,
+                &[Some(Mpsse), Some(Mpsse), Some(Uart), Some(Uart)],
+            ),
+            (DeviceType::FT232H, &[Some(Mpsse), None]),
+            (DeviceType::FT232R, &[Some(Uart), None]),
+        ] {
+            for (num, kind) in kinds.iter().enumerate() {
+                assert_eq!(
+                    device_type.interface_kind(num as u8).map(|kind| kind as u8),
+                    kind.map(|kind| kind as u8)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::VecDeque;
+
+use event_listener::Event;
+
+use crate::FtdiPacketDecoder;
+
+const LINE_ERRORS: u8 = 0x1e;
+const MAX_PENDING_EVENTS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEvent {
+    Overrun,
+    Parity,
+    Framing,
+    Break,
+}
+
+impl LineEvent {
+    fn from_bit(bit: u8) -> Option<Self> {
+        match bit {
+            0x02 => Some(LineEvent::Overrun),
+            0x04 => Some(LineEvent::Parity),
+            0x08 => Some(LineEvent::Framing),
+            0x10 => Some(LineEvent::Break),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    events: VecDeque<LineEvent>,
+    last: u8,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct LineMonitor {
+    state: std::sync::Mutex<State>,
+    event: Event,
+}
+
+impl LineMonitor {
+    pub(crate) fn record(&self, status: [u8; 2]) {
+        let line = status[1] & LINE_ERRORS;
+
+        let mut state = self.state.lock().unwrap();
+        let raised = line & !state.last;
+        state.last = line;
+        if raised == 0 {
+            return;
+        }
+
+        for event in (0..8).filter_map(|i| LineEvent::from_bit(raised & (1 << i))) {
+            if state.events.len() >= MAX_PENDING_EVENTS {
+                state.events.pop_front();
+            }
+            state.events.push_back(event);
+        }
+        drop(state);
+
+        self.event.notify(usize::MAX);
+    }
+
+    pub(crate) fn record_packets(&self, buffer: &[u8], decoder: FtdiPacketDecoder) {
+        for packet in decoder.packets(buffer) {
+            self.record(packet.status);
+        }
+    }
+
+    pub(crate) async fn next(&self) -> LineEvent {
+        loop {
+            let listener = self.event.listen();
+            if let Some(event) = self.state.lock().unwrap().events.pop_front() {
+                return event;
+            }
+
+            listener.await;
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::mpsse::{MpsseCmdBuilder, MpsseInterface};
+use crate::{Bitmode, Error, Interface, Result};
+
+const MCU_BLOCK_CHUNK: usize = 256;
+
+#[derive(Clone, Debug)]
+pub struct McuHostBus {
+    interface: Interface,
+}
+
+impl McuHostBus {
+    pub async fn new(interface: Interface) -> Result<Self> {
+        interface.set_bitmode(0, Bitmode::Reset).await?;
+        interface.set_bitmode(0, Bitmode::Mcu).await?;
+        interface.purge_all().await?;
+
+        Ok(McuHostBus { interface })
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub async fn read8(&self, address: u8) -> Result<u8> {
+        self.read(MpsseCmdBuilder::new().mcu_read_short(address))
+            .await
+    }
+
+    pub async fn read16(&self, address: u16) -> Result<u8> {
+        self.read(MpsseCmdBuilder::new().mcu_read_extended(address))
+            .await
+    }
+
+    pub async fn write8(&self, address: u8, data: u8) -> Result<()> {
+        self.interface
+            .execute(MpsseCmdBuilder::new().mcu_write_short(address, data))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn write16(&self, address: u16, data: u8) -> Result<()> {
+        self.interface
+            .execute(MpsseCmdBuilder::new().mcu_write_extended(address, data))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn read_block(&self, address: u16, buf: &mut [u8]) -> Result<()> {
+        check_range(address, buf.len())?;
+
+        for (i, chunk) in buf.chunks_mut(MCU_BLOCK_CHUNK).enumerate() {
+            let start = address + (i * MCU_BLOCK_CHUNK) as u16;
+            let cmd = (0..chunk.len() as u16).fold(MpsseCmdBuilder::new(), |cmd, offset| {
+                cmd.mcu_read_extended(start + offset)
+            });
+
+            let res = self.interface.execute(cmd).await?;
+            chunk.copy_from_slice(&res);
+        }
+
+        Ok(())
+    }
+
+    pub async fn write_block(&self, address: u16, data: &[u8]) -> Result<()> {
+        check_range(address, data.len())?;
+
+        for (i, chunk) in data.chunks(MCU_BLOCK_CHUNK).enumerate() {
+            let start = address + (i * MCU_BLOCK_CHUNK) as u16;
+            let cmd = chunk
+                .iter()
+                .enumerate()
+                .fold(MpsseCmdBuilder::new(), |cmd, (offset, &byte)| {
+                    cmd.mcu_write_extended(start + offset as u16, byte)
+                });
+
+            self.interface.execute(cmd).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn read(&self, cmd: MpsseCmdBuilder) -> Result<u8> {
+        let res = self.interface.execute(cmd).await?;
+
+        match res.first() {
+            Some(&data) => Ok(data),
+            None => Err(Error::InvalidResponse(res)),
+        }
+    }
+
+    pub async fn exit(self) -> Result<Interface> {
+        self.interface.set_bitmode(0, Bitmode::Reset).await?;
+
+        Ok(self.interface)
+    }
+}
+
+fn check_range(address: u16, len: usize) -> Result<()> {
+    if address as usize + len > 0x10000 {
+        return Err(Error::InvalidParameter(format!(
+            "block of {} bytes at {:#06x} exceeds the 16-bit address space",
+            len, address
+        )));
+    }
+
+    Ok(())
+}
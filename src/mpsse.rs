@@ -1,34 +1,75 @@
-use anyhow::Result;
+pub mod transaction;
+pub mod vectored;
 
-use crate::DeviceType;
+use core::future::Future;
+use core::time::Duration;
+use std::time::Instant;
+
+pub use kftdi_core::mpsse::*;
+
+use crate::gpio::{Bank, Direction};
+use crate::selftest::{self, SelfTestReport};
+use crate::{DeviceType, Error, Result};
 
-#[async_trait::async_trait]
 pub trait MpsseInterface {
-    async fn initialize_mpsse(&self) -> Result<()>;
-    async fn synchronize_mpsse(&self) -> Result<()>;
-    async fn set_low_data_bits(&self, value: u8, direction: u8) -> Result<()>;
-    async fn set_high_data_bits(&self, value: u8, direction: u8) -> Result<()>;
-    async fn enable_3phase_clocking(&self) -> Result<()>;
-    async fn disable_3phase_clocking(&self) -> Result<()>;
-    async fn set_frequency(&self, frequency: u32) -> Result<()>;
-    async fn set_clock(&self, divisor: u16, clkdiv: Option<bool>) -> Result<()>;
-    fn clock_divisor(&self, frequency: u32) -> (u16, Option<bool>);
-}
-
-#[async_trait::async_trait]
+    fn initialize_mpsse(&self) -> impl Future<Output = Result<()>> + Send;
+    fn initialize_mpsse_with(
+        &self,
+        settings: MpsseSettings,
+    ) -> impl Future<Output = Result<u32>> + Send;
+    fn synchronize_mpsse(&self) -> impl Future<Output = Result<()>> + Send;
+    fn set_low_data_bits(
+        &self,
+        value: u8,
+        direction: u8,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn set_high_data_bits(
+        &self,
+        value: u8,
+        direction: u8,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn get_low_data_bits(&self) -> impl Future<Output = Result<DataBits>> + Send;
+    fn get_high_data_bits(&self) -> impl Future<Output = Result<DataBits>> + Send;
+    fn read_gpio(&self) -> impl Future<Output = Result<u16>> + Send;
+    fn execute(&self, cmd: MpsseCmdBuilder) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    fn flush(&self, len: usize) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    fn enable_3phase_clocking(&self) -> impl Future<Output = Result<()>> + Send;
+    fn disable_3phase_clocking(&self) -> impl Future<Output = Result<()>> + Send;
+    fn enable_adaptive_clocking(&self) -> impl Future<Output = Result<()>> + Send;
+    fn disable_adaptive_clocking(&self) -> impl Future<Output = Result<()>> + Send;
+    fn set_open_drain(
+        &self,
+        low_mask: u8,
+        high_mask: u8,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn set_frequency(&self, frequency: u32) -> impl Future<Output = Result<u32>> + Send;
+    /// Fails if the achievable frequency deviates from `frequency` by more
+    /// than `tolerance` percent.
+    fn set_frequency_exact(
+        &self,
+        frequency: u32,
+        tolerance: u32,
+    ) -> impl Future<Output = Result<u32>> + Send;
+    fn set_clock(
+        &self,
+        divisor: u16,
+        clkdiv: Option<bool>,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn clock_divisor(&self, frequency: u32) -> Result<(u16, Option<bool>)>;
+}
+
 impl MpsseInterface for crate::Interface {
-    fn clock_divisor(&self, frequency: u32) -> (u16, Option<bool>) {
-        match self.device_type {
-            DeviceType::FT2232C => ((6_000_000 / frequency - 1) as u16, None),
-            DeviceType::FT2232H | DeviceType::FT4232H | DeviceType::FT232H => {
-                if frequency <= 6_000_000 {
-                    ((6_000_000 / frequency - 1) as u16, Some(true))
-                } else {
-                    ((30_000_000 / frequency - 1) as u16, Some(false))
-                }
-            }
-            _ => panic!("Unknown device type: {:?}", self.device_type),
+    fn clock_divisor(&self, frequency: u32) -> Result<(u16, Option<bool>)> {
+        if frequency == 0 {
+            return Err(Error::InvalidParameter(
+                "mpsse frequency must be non-zero".into(),
+            ));
         }
+
+        Ok(kftdi_core::clock::clock_divisor(
+            high_speed(self.device_type)?,
+            frequency,
+        ))
     }
 
     async fn initialize_mpsse(&self) -> Result<()> {
@@ -38,28 +79,93 @@ impl MpsseInterface for crate::Interface {
         self.purge_all().await?;
         self.synchronize_mpsse().await?;
         self.purge_all().await?;
+        *self.mpsse_state.lock().unwrap() = MpsseState::default();
 
         Ok(())
     }
 
-    async fn synchronize_mpsse(&self) -> Result<()> {
-        self.write_all(vec![EnableLoopback::byte(), Synchronize::byte(), DisableLoopback::byte()]).await?;
+    async fn initialize_mpsse_with(&self, settings: MpsseSettings) -> Result<u32> {
+        for (requested, setting) in [
+            (settings.three_phase, "3-phase clocking"),
+            (settings.adaptive_clocking, "adaptive clocking"),
+        ] {
+            if requested {
+                require_h_series(self, setting)?;
+            }
+        }
+        if settings.drive_only_zero_mask.is_some() {
+            require_drive_only_zero(self)?;
+        }
 
-        let mut buf = [0u8; 2];
-        self.read_all(&mut buf).await?;
+        self.initialize_mpsse().await?;
+
+        if let Some(latency) = settings.latency {
+            self.set_latency_timer(latency).await?;
+        }
+
+        match settings.three_phase {
+            true => self.enable_3phase_clocking().await?,
+            false => self.disable_3phase_clocking().await?,
+        }
 
-        if !(buf[0] == 0xfa && buf[1] == Synchronize::byte()) {
-            return Err(anyhow::Error::msg(format!("invalid synchronization byte {:x?}", buf)));
+        match settings.adaptive_clocking {
+            true => self.enable_adaptive_clocking().await?,
+            false => self.disable_adaptive_clocking().await?,
+        }
+
+        let loopback = match settings.loopback {
+            true => EnableLoopback::byte(),
+            false => DisableLoopback::byte(),
+        };
+        self.transact(vec![loopback], &mut []).await?;
+
+        if let Some((low, high)) = settings.drive_only_zero_mask {
+            self.set_open_drain(low, high).await?;
+        }
+
+        self.set_frequency(settings.frequency).await
+    }
+
+    async fn synchronize_mpsse(&self) -> Result<()> {
+        let mut buf = [0u8; 2];
+        self.transact(
+            vec![
+                EnableLoopback::byte(),
+                Synchronize::byte(),
+                DisableLoopback::byte(),
+            ],
+            &mut buf,
+        )
+        .await?;
+
+        if !(buf[0] == BAD_COMMAND && buf[1] == Synchronize::byte()) {
+            return Err(Error::MpsseSync(buf));
         }
 
         Ok(())
     }
 
-    async fn set_frequency(&self, frequency: u32) -> Result<()> {
-        let (divisor, clkdiv) = self.clock_divisor(frequency);
+    async fn set_frequency(&self, frequency: u32) -> Result<u32> {
+        let (divisor, clkdiv, actual) = frequency_plan(self, frequency)?;
         self.set_clock(divisor, clkdiv).await?;
 
-        Ok(())
+        Ok(actual)
+    }
+
+    async fn set_frequency_exact(&self, frequency: u32, tolerance: u32) -> Result<u32> {
+        let (divisor, clkdiv, actual) = frequency_plan(self, frequency)?;
+
+        let deviation = (actual as u64).abs_diff(frequency as u64) * 100;
+        if deviation > tolerance as u64 * frequency as u64 {
+            return Err(Error::FrequencyDeviation {
+                requested: frequency,
+                actual,
+            });
+        }
+
+        self.set_clock(divisor, clkdiv).await?;
+
+        Ok(actual)
     }
 
     async fn set_clock(&self, divisor: u16, clkdiv: Option<bool>) -> Result<()> {
@@ -74,110 +180,655 @@ impl MpsseInterface for crate::Interface {
         cmd.push(SetClockFrequency::byte());
         cmd.extend_from_slice(&divisor.to_le_bytes());
 
-        self.write_all(cmd).await?;
+        self.transact(cmd, &mut []).await?;
+        self.mpsse_state.lock().unwrap().clock = Some((divisor, clkdiv));
 
         Ok(())
     }
 
     async fn enable_3phase_clocking(&self) -> Result<()> {
-        self.write_all(vec![Enable3PhaseClocking::byte()]).await?;
+        require_h_series(self, "3-phase clocking")?;
+        self.transact(vec![Enable3PhaseClocking::byte()], &mut [])
+            .await?;
+        self.mpsse_state.lock().unwrap().three_phase = true;
 
         Ok(())
     }
 
     async fn disable_3phase_clocking(&self) -> Result<()> {
-        self.write_all(vec![Disable3PhaseClocking::byte()]).await?;
+        if is_h_series(self.device_type) {
+            self.transact(vec![Disable3PhaseClocking::byte()], &mut [])
+                .await?;
+        }
+        self.mpsse_state.lock().unwrap().three_phase = false;
+
+        Ok(())
+    }
+
+    async fn enable_adaptive_clocking(&self) -> Result<()> {
+        require_h_series(self, "adaptive clocking")?;
+        self.transact(vec![EnableAdaptiveClocking::byte()], &mut [])
+            .await?;
+        self.mpsse_state.lock().unwrap().adaptive_clocking = true;
+
+        Ok(())
+    }
+
+    async fn disable_adaptive_clocking(&self) -> Result<()> {
+        if is_h_series(self.device_type) {
+            self.transact(vec![DisableAdaptiveClocking::byte()], &mut [])
+                .await?;
+        }
+        self.mpsse_state.lock().unwrap().adaptive_clocking = false;
+
+        Ok(())
+    }
+
+    async fn set_open_drain(&self, low_mask: u8, high_mask: u8) -> Result<()> {
+        require_drive_only_zero(self)?;
+        self.transact(
+            vec![EnableDriveOnlyZero::byte(), low_mask, high_mask],
+            &mut [],
+        )
+        .await?;
+        self.mpsse_state.lock().unwrap().drive_only_zero = Some((low_mask, high_mask));
 
         Ok(())
     }
 
     async fn set_low_data_bits(&self, value: u8, direction: u8) -> Result<()> {
-        self.write_all(vec![SetDataBitsLowByte::byte(), value, direction]).await?;
+        let mut state = self.pins.state.lock().await;
+        self.write_data_bits(Bank::Low, value, direction).await?;
+        state[Bank::Low as usize] = (value, direction);
 
         Ok(())
     }
 
     async fn set_high_data_bits(&self, value: u8, direction: u8) -> Result<()> {
-        self.write_all(vec![SetDataBitsHighByte::byte(), value, direction]).await?;
+        let mut state = self.pins.state.lock().await;
+        self.write_data_bits(Bank::High, value, direction).await?;
+        state[Bank::High as usize] = (value, direction);
+
+        Ok(())
+    }
+
+    async fn get_low_data_bits(&self) -> Result<DataBits> {
+        let mut buf = [0u8; 1];
+        self.transact(
+            vec![GetDataBitsLowByte::byte(), SendImmediate::byte()],
+            &mut buf,
+        )
+        .await?;
+
+        Ok(DataBits(buf[0]))
+    }
+
+    async fn get_high_data_bits(&self) -> Result<DataBits> {
+        let mut buf = [0u8; 1];
+        self.transact(
+            vec![GetDataBitsHighByte::byte(), SendImmediate::byte()],
+            &mut buf,
+        )
+        .await?;
+
+        Ok(DataBits(buf[0]))
+    }
+
+    async fn read_gpio(&self) -> Result<u16> {
+        let mut buf = [0u8; 2];
+        self.transact(
+            vec![
+                GetDataBitsLowByte::byte(),
+                GetDataBitsHighByte::byte(),
+                SendImmediate::byte(),
+            ],
+            &mut buf,
+        )
+        .await?;
+
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    async fn execute(&self, cmd: MpsseCmdBuilder) -> Result<Vec<u8>> {
+        let _guard = self.command_lock.lock().await;
+        execute_locked(self, cmd).await
+    }
+
+    async fn flush(&self, len: usize) -> Result<Vec<u8>> {
+        let mut res = vec![0u8; len];
+        self.transact(vec![SendImmediate::byte()], &mut res).await?;
+
+        Ok(res)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MpsseSettings {
+    pub frequency: u32,
+    pub three_phase: bool,
+    pub adaptive_clocking: bool,
+    pub drive_only_zero_mask: Option<(u8, u8)>,
+    pub loopback: bool,
+    pub latency: Option<Duration>,
+}
+
+impl Default for MpsseSettings {
+    fn default() -> Self {
+        MpsseSettings {
+            frequency: 1_000_000,
+            three_phase: false,
+            adaptive_clocking: false,
+            drive_only_zero_mask: None,
+            loopback: false,
+            latency: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct MpsseState {
+    pub(crate) clock: Option<(u16, Option<bool>)>,
+    pub(crate) three_phase: bool,
+    pub(crate) adaptive_clocking: bool,
+    pub(crate) drive_only_zero: Option<(u8, u8)>,
+}
+
+impl MpsseState {
+    pub(crate) async fn replay(self, interface: &crate::Interface) -> Result<()> {
+        if self.three_phase {
+            interface.enable_3phase_clocking().await?;
+        }
+        if self.adaptive_clocking {
+            interface.enable_adaptive_clocking().await?;
+        }
+        if let Some((low, high)) = self.drive_only_zero {
+            interface.set_open_drain(low, high).await?;
+        }
+        if let Some((divisor, clkdiv)) = self.clock {
+            interface.set_clock(divisor, clkdiv).await?;
+        }
 
         Ok(())
     }
 }
 
-macro_rules! mpsse_commands {
-    ($($cmd: ident { cmd: $cmd_byte:literal$(,)?$($field_name:ident: $field_type:ty),* }),*$(,)?) => {
-        #[repr(u8)]
-        pub enum CommandByte {
-            $($cmd = $cmd_byte,)*
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockOutput {
+    Cycles(usize),
+    Continuous { until_high: bool },
+}
+
+#[derive(Clone, Debug)]
+pub struct Mpsse {
+    interface: crate::Interface,
+}
+
+impl Mpsse {
+    pub(crate) async fn new(interface: crate::Interface) -> Result<Self> {
+        interface.initialize_mpsse().await?;
+        *interface.pins.state.lock().await = Default::default();
+        Ok(Mpsse { interface })
+    }
+
+    pub fn interface(&self) -> &crate::Interface {
+        &self.interface
+    }
+
+    pub fn into_inner(self) -> crate::Interface {
+        self.interface
+    }
+
+    pub async fn output_clock(&self, frequency: u32, output: ClockOutput) -> Result<()> {
+        self.interface.set_frequency(frequency).await?;
+        self.set_pin_direction(0, Direction::Output).await?;
+
+        let cmd = match output {
+            ClockOutput::Cycles(cycles) => MpsseCmdBuilder::new().delay_clocks(cycles),
+            ClockOutput::Continuous { until_high } => {
+                MpsseCmdBuilder::new().clock_until_io(until_high)
+            }
+        };
+        self.interface.execute(cmd.get_low_data_bits()).await?;
+
+        Ok(())
+    }
+
+    pub async fn selftest_loopback(&self, frequency: u32) -> Result<SelfTestReport> {
+        let pattern = selftest::pattern(selftest::PATTERN_LEN);
+        self.interface.set_frequency(frequency).await?;
+
+        let start = Instant::now();
+        let cmd = MpsseCmdBuilder::new()
+            .enable_loopback()
+            .clock_bytes_inout(ClockEdge::Falling, BitOrder::MsbFirst, &pattern)
+            .disable_loopback();
+        let res = self.interface.execute(cmd).await?;
+
+        Ok(SelfTestReport::compare(&pattern, &res, start.elapsed()))
+    }
+
+    pub async fn wait_for_pin_high(&self, pin: u8) -> Result<()> {
+        self.wait_for_pin(pin, true).await
+    }
+
+    pub async fn wait_for_pin_low(&self, pin: u8) -> Result<()> {
+        self.wait_for_pin(pin, false).await
+    }
+
+    async fn wait_for_pin(&self, pin: u8, high: bool) -> Result<()> {
+        if pin > 15 {
+            return Err(Error::InvalidParameter(format!(
+                "gpio pin must be in 0..=15, got {}",
+                pin
+            )));
         }
 
-        $(
-            #[repr(C, align(8))]
-            #[derive(Copy, Clone, Default, Eq, PartialEq)]
-            pub struct $cmd;
+        if pin == GPIOL1 {
+            let cmd = match high {
+                true => MpsseCmdBuilder::new().wait_on_io_high(),
+                false => MpsseCmdBuilder::new().wait_on_io_low(),
+            };
+            self.interface.execute(cmd.get_low_data_bits()).await?;
 
-            impl $cmd {
-                pub fn byte() -> u8 {
-                    $cmd_byte
-                }
+            return Ok(());
+        }
+
+        while (self.interface.read_gpio().await? & (1 << pin) != 0) != high {
+            futures_timer::Delay::new(WAIT_POLL_INTERVAL).await;
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_pin(&self, pin: u8, high: bool) -> Result<()> {
+        self.update_pin(pin, |value, _, mask| {
+            if high {
+                *value |= mask;
+            } else {
+                *value &= !mask;
             }
-        )*
+        })
+        .await
+    }
+
+    pub async fn set_pin_direction(&self, pin: u8, direction: Direction) -> Result<()> {
+        self.update_pin(pin, |_, dir, mask| match direction {
+            Direction::Output => *dir |= mask,
+            Direction::Input => *dir &= !mask,
+        })
+        .await
+    }
+
+    pub async fn toggle_pin(&self, pin: u8) -> Result<()> {
+        self.update_pin(pin, |value, _, mask| *value ^= mask).await
+    }
+
+    async fn update_pin(&self, pin: u8, update: impl FnOnce(&mut u8, &mut u8, u8)) -> Result<()> {
+        if pin > 15 {
+            return Err(Error::InvalidParameter(format!(
+                "gpio pin must be in 0..=15, got {}",
+                pin
+            )));
+        }
+
+        if let Some(owner) = self.interface.pin_owner(pin) {
+            return Err(Error::PinConflict { pin, owner });
+        }
+
+        let bank = match pin {
+            0..=7 => Bank::Low,
+            _ => Bank::High,
+        };
+        let mut state = self.interface.pins.state.lock().await;
+        let (mut value, mut direction) = state[bank as usize];
+        update(&mut value, &mut direction, 1 << (pin % 8));
+
+        self.interface
+            .write_data_bits(bank, value, direction)
+            .await?;
+        state[bank as usize] = (value, direction);
+
+        Ok(())
+    }
+}
+
+impl MpsseInterface for Mpsse {
+    fn clock_divisor(&self, frequency: u32) -> Result<(u16, Option<bool>)> {
+        self.interface.clock_divisor(frequency)
+    }
+
+    async fn initialize_mpsse(&self) -> Result<()> {
+        self.interface.initialize_mpsse().await
+    }
+
+    async fn initialize_mpsse_with(&self, settings: MpsseSettings) -> Result<u32> {
+        self.interface.initialize_mpsse_with(settings).await
+    }
+
+    async fn synchronize_mpsse(&self) -> Result<()> {
+        self.interface.synchronize_mpsse().await
+    }
+
+    async fn set_frequency(&self, frequency: u32) -> Result<u32> {
+        self.interface.set_frequency(frequency).await
+    }
+
+    async fn set_frequency_exact(&self, frequency: u32, tolerance: u32) -> Result<u32> {
+        self.interface
+            .set_frequency_exact(frequency, tolerance)
+            .await
+    }
+
+    async fn set_clock(&self, divisor: u16, clkdiv: Option<bool>) -> Result<()> {
+        self.interface.set_clock(divisor, clkdiv).await
+    }
+
+    async fn enable_3phase_clocking(&self) -> Result<()> {
+        self.interface.enable_3phase_clocking().await
+    }
+
+    async fn disable_3phase_clocking(&self) -> Result<()> {
+        self.interface.disable_3phase_clocking().await
+    }
+
+    async fn enable_adaptive_clocking(&self) -> Result<()> {
+        self.interface.enable_adaptive_clocking().await
+    }
+
+    async fn disable_adaptive_clocking(&self) -> Result<()> {
+        self.interface.disable_adaptive_clocking().await
+    }
+
+    async fn set_open_drain(&self, low_mask: u8, high_mask: u8) -> Result<()> {
+        self.interface.set_open_drain(low_mask, high_mask).await
+    }
+
+    async fn set_low_data_bits(&self, value: u8, direction: u8) -> Result<()> {
+        self.interface.set_low_data_bits(value, direction).await
+    }
+
+    async fn set_high_data_bits(&self, value: u8, direction: u8) -> Result<()> {
+        self.interface.set_high_data_bits(value, direction).await
+    }
+
+    async fn get_low_data_bits(&self) -> Result<DataBits> {
+        self.interface.get_low_data_bits().await
+    }
+
+    async fn get_high_data_bits(&self) -> Result<DataBits> {
+        self.interface.get_high_data_bits().await
+    }
+
+    async fn read_gpio(&self) -> Result<u16> {
+        self.interface.read_gpio().await
+    }
+
+    async fn execute(&self, cmd: MpsseCmdBuilder) -> Result<Vec<u8>> {
+        self.interface.execute(cmd).await
+    }
+
+    async fn flush(&self, len: usize) -> Result<Vec<u8>> {
+        self.interface.flush(len).await
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DataBits(pub u8);
+
+impl DataBits {
+    pub fn is_high(&self, pin: u8) -> bool {
+        Self::mask(pin).is_some_and(|mask| self.0 & mask != 0)
+    }
+
+    pub fn is_low(&self, pin: u8) -> bool {
+        Self::mask(pin).is_some_and(|mask| self.0 & mask == 0)
+    }
+
+    fn mask(pin: u8) -> Option<u8> {
+        1u8.checked_shl(pin as u32)
+    }
+}
+
+const GPIOL1: u8 = 5;
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+const BAD_COMMAND: u8 = 0xfa;
+
+fn is_h_series(device_type: DeviceType) -> bool {
+    matches!(
+        device_type,
+        DeviceType::FT2232H | DeviceType::FT4232H | DeviceType::FT232H
+    )
+}
+
+fn require_h_series(interface: &crate::Interface, setting: &str) -> Result<()> {
+    match is_h_series(interface.device_type) {
+        true => Ok(()),
+        false => Err(Error::InvalidParameter(format!(
+            "{} is not supported on {:?}",
+            setting, interface.device_type
+        ))),
+    }
+}
+
+fn require_drive_only_zero(interface: &crate::Interface) -> Result<()> {
+    match interface.device_type {
+        DeviceType::FT232H => Ok(()),
+        device_type => Err(Error::InvalidParameter(format!(
+            "drive-only-zero is not supported on {:?}",
+            device_type
+        ))),
+    }
+}
+
+fn high_speed(device_type: DeviceType) -> Result<bool> {
+    match device_type {
+        DeviceType::FT2232C => Ok(false),
+        DeviceType::FT2232H | DeviceType::FT4232H | DeviceType::FT232H => Ok(true),
+        device_type => Err(Error::InvalidParameter(format!(
+            "{:?} has no mpsse engine",
+            device_type
+        ))),
+    }
+}
+
+fn frequency_plan(
+    interface: &crate::Interface,
+    frequency: u32,
+) -> Result<(u16, Option<bool>, u32)> {
+    let high_speed = high_speed(interface.device_type)?;
+
+    let three_phase = interface.mpsse_state.lock().unwrap().three_phase;
+    let (programmed, effective): (u32, fn(u32) -> u32) = match three_phase {
+        true => (frequency.saturating_mul(3) / 2, |hz: u32| hz * 2 / 3),
+        false => (frequency, |hz: u32| hz),
     };
+
+    let (min, max) = kftdi_core::clock::frequency_range(high_speed);
+    if !(min..=max).contains(&programmed) {
+        return Err(Error::InvalidParameter(format!(
+            "mpsse frequency {} outside {}..={} Hz for {:?} (3-phase clocking: {})",
+            frequency,
+            effective(min) + three_phase as u32,
+            effective(max),
+            interface.device_type,
+            three_phase
+        )));
+    }
+
+    let (divisor, clkdiv) = interface.clock_divisor(programmed)?;
+    let actual = kftdi_core::clock::actual_frequency(divisor, clkdiv);
+
+    Ok((divisor, clkdiv, effective(actual)))
 }
 
-mpsse_commands! {
-    SetDataBitsLowByte { cmd: 0x80, value: u8, direction: u8 },
-    GetDataBitsLowByte { cmd: 0x81 },
-    SetDataBitsHighByte { cmd: 0x82, value: u8, direction: u8 },
-    GetDataBitsHighByte { cmd: 0x83 },
-    EnableLoopback { cmd: 0x84 },
-    DisableLoopback { cmd: 0x85 },
-    SetClockFrequency { cmd: 0x86 },
-    SendImmediate { cmd: 0x87 },
-    WaitOnIOHigh { cmd: 0x88 },
-    WaitOnIOLow { cmd: 0x89 },
-    DisableClockDivide { cmd: 0x8A },
-    EnableClockDivide { cmd: 0x8B },
-    Enable3PhaseClocking { cmd: 0x8C },
-    Disable3PhaseClocking { cmd: 0x8D },
-    DelayBits { cmd: 0x8E },
-    DelayBytes { cmd: 0x8F, },
-    EnableAdaptiveClocking { cmd: 0x96 },
-    DisableAdaptiveClocking { cmd: 0x97 },
-    EnableDriveOnlyZero { cmd: 0x9E },
-
-    WriteBytesPosLsb { cmd: 0x18 },
-    WriteBytesNegLsb { cmd: 0x19 },
-    WriteBitsPosLsb { cmd: 0x1A },
-    WriteBitsNegLsb { cmd: 0x1B },
-    ReadBytesPosLsb { cmd: 0x28, length: u16 },
-    ReadBitsPosLsb { cmd: 0x2A, length: u16 },
-    ReadBytesNegLsb { cmd: 0x2C, length: u16 },
-    ReadBitsNegLsb { cmd: 0x2E, length: u16 },
-    WriteBytesNegReadPosLsb { cmd: 0x39 },
-    WriteBitsNegReadPosLsb { cmd: 0x3B },
-    WriteBytesPosReadNegLsb { cmd: 0x3C },
-    WriteBitsPosReadNegLsb { cmd: 0x3E },
-
-    WriteBytesPosMsb { cmd: 0x10 },
-    WriteBytesNegMsb { cmd: 0x11 },
-    WriteBitsPosMsb { cmd: 0x12 },
-    WriteBitsNegMsb { cmd: 0x13 },
-    ReadBytesPosMsb { cmd: 0x20, length: u16 },
-    ReadBitsPosMsb { cmd: 0x22, length: u16 },
-    ReadBitsNegMsb { cmd: 0x26, length: u16 },
-    ReadBytesNegMsb { cmd: 0x24, length: u16 },
-    WriteBytesNegReadPosMsb { cmd: 0x31 },
-    WriteBytesPosReadNegMsb { cmd: 0x34 },
-    WriteBitsNegReadPosMsb { cmd: 0x33 },
-    WriteBitsPosReadNegMsb { cmd: 0x36 },
-
-    WriteTmsBitsPos { cmd: 0x4A, length: u8, byte: u8  },
-    WriteTmsBitsNeg { cmd: 0x4B, length: u8, byte: u8  },
-    WriteTmsBitsPosReadPos { cmd: 0x6A, length: u8, byte: u8 },
-    WriteTmsBitsPosReadNeg { cmd: 0x6E, length: u8, byte: u8 },
-    WriteTmsBitsNegReadPos { cmd: 0x6B, length: u8, byte: u8 },
-    WriteTmsBitsNegReadNeg { cmd: 0x6F, length: u8, byte: u8 },
-
-    Synchronize { cmd: 0xAB },
+async fn execute_locked(interface: &crate::Interface, cmd: MpsseCmdBuilder) -> Result<Vec<u8>> {
+    match execute_commands(interface, cmd).await {
+        Err(Error::Timeout {
+            requested,
+            completed,
+            ..
+        }) => {
+            interface.purge_rx().await?;
+            Err(Error::Timeout {
+                operation: "mpsse execute",
+                requested,
+                completed,
+            })
+        }
+        res => res,
+    }
+}
+
+async fn execute_commands(interface: &crate::Interface, cmd: MpsseCmdBuilder) -> Result<Vec<u8>> {
+    let steps = command_responses(cmd.as_slice(), interface.device_type);
+    let segments = response_segments(&steps);
+    let len = segments.iter().map(|&(_, len)| len).sum();
+    let mut res = vec![0u8; len];
+
+    if segments.len() <= 1 {
+        let cmd = if len > 0 {
+            cmd.request_immediate()
+        } else {
+            cmd
+        };
+
+        interface.write_mpsse(cmd.into_bytes()).await?;
+        interface.read_all(&mut res).await?;
+
+        return parse_response(&steps, res);
+    }
+
+    let bytes = cmd.into_bytes();
+    let (mut cmd_pos, mut res_pos) = (0, 0);
+
+    for (cmd_end, res_len) in segments {
+        let mut segment = bytes[cmd_pos..cmd_end].to_vec();
+        if res_len > 0 {
+            segment.push(SendImmediate::byte());
+        }
+
+        interface.write_mpsse(segment).await?;
+        interface
+            .read_all(&mut res[res_pos..res_pos + res_len])
+            .await?;
+
+        cmd_pos = cmd_end;
+        res_pos += res_len;
+    }
+
+    parse_response(&steps, res)
+}
+
+#[derive(Debug)]
+pub struct Exclusive<'a> {
+    interface: &'a crate::Interface,
+    _guard: async_lock::MutexGuard<'a, ()>,
+}
+
+impl<'a> Exclusive<'a> {
+    pub(crate) fn new(
+        interface: &'a crate::Interface,
+        guard: async_lock::MutexGuard<'a, ()>,
+    ) -> Self {
+        Exclusive {
+            interface,
+            _guard: guard,
+        }
+    }
+
+    pub async fn execute(&self, cmd: MpsseCmdBuilder) -> Result<Vec<u8>> {
+        execute_locked(self.interface, cmd).await
+    }
+
+    pub async fn write(&self, cmd: Vec<u8>) -> Result<()> {
+        self.interface.write_mpsse(cmd).await
+    }
+
+    pub async fn read(&self, buf: &mut [u8]) -> Result<()> {
+        self.interface.read_all(buf).await
+    }
+
+    pub async fn read_exact_timeout(&self, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        self.interface.read_exact_timeout(buf, timeout).await
+    }
+
+    pub async fn purge_rx(&self) -> Result<()> {
+        self.interface.purge_rx().await
+    }
+}
+
+type Step = (core::result::Result<usize, u8>, usize);
+
+fn command_responses(cmd: &[u8], device_type: DeviceType) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut pos = 0;
+
+    while pos < cmd.len() {
+        match Command::decode(&cmd[pos..]) {
+            Some((command, len)) if is_supported(&command, device_type) => {
+                steps.push((Ok(command.response_len()), len));
+                pos += len;
+            }
+            _ => {
+                steps.push((Err(cmd[pos]), 1));
+                pos += 1;
+            }
+        }
+    }
+
+    steps
+}
+
+fn response_segments(steps: &[Step]) -> Vec<(usize, usize)> {
+    let mut segments = Vec::new();
+    let (mut cmd_pos, mut res_len) = (0, 0);
+
+    for &(step, cmd_len) in steps {
+        let step_len = step.unwrap_or(2);
+        if res_len > 0 && res_len + step_len > MAX_CLOCK_BYTES {
+            segments.push((cmd_pos, res_len));
+            res_len = 0;
+        }
+
+        cmd_pos += cmd_len;
+        res_len += step_len;
+    }
+
+    if cmd_pos > segments.last().map_or(0, |&(end, _)| end) {
+        segments.push((cmd_pos, res_len));
+    }
+
+    segments
+}
+
+fn parse_response(steps: &[Step], response: Vec<u8>) -> Result<Vec<u8>> {
+    let mut pos = 0;
+    let mut bad = None;
+
+    for (step, _) in steps {
+        match *step {
+            Ok(len) => pos += len,
+            Err(opcode) => {
+                if response.get(pos..pos + 2) != Some(&[BAD_COMMAND, opcode][..]) {
+                    return Err(Error::InvalidResponse(response));
+                }
+
+                bad.get_or_insert(opcode);
+                pos += 2;
+            }
+        }
+    }
+
+    match bad {
+        Some(opcode) => Err(Error::BadMpsseCommand { opcode }),
+        None => Ok(response),
+    }
+}
+
+pub fn is_supported(command: &Command, device_type: DeviceType) -> bool {
+    match device_type {
+        DeviceType::FT2232C => !matches!(command.byte(), 0x8a..=0x8f | 0x94..=0x97 | 0x9e),
+        _ => true,
+    }
 }
@@ -0,0 +1,110 @@
+use core::time::Duration;
+
+use super::{BitOrder, MpsseCmdBuilder, MpsseInterface};
+use crate::spi::SpiMode;
+use crate::{Error, Result};
+
+#[derive(Clone, Debug)]
+pub struct MpsseTransaction {
+    cmd: MpsseCmdBuilder,
+    frequency: u32,
+    mode: SpiMode,
+    order: BitOrder,
+    reads: Vec<usize>,
+}
+
+impl MpsseTransaction {
+    pub fn new(frequency: u32) -> Self {
+        MpsseTransaction {
+            cmd: MpsseCmdBuilder::new(),
+            frequency,
+            mode: SpiMode::Mode0,
+            order: BitOrder::MsbFirst,
+            reads: Vec::new(),
+        }
+    }
+
+    pub fn spi_mode(mut self, mode: SpiMode, order: BitOrder) -> Self {
+        self.mode = mode;
+        self.order = order;
+        self
+    }
+
+    pub fn gpio_set_low(self, value: u8, direction: u8) -> Self {
+        self.push(|cmd| cmd.set_low_data_bits(value, direction))
+    }
+
+    pub fn gpio_set_high(self, value: u8, direction: u8) -> Self {
+        self.push(|cmd| cmd.set_high_data_bits(value, direction))
+    }
+
+    pub fn gpio_read_low(self) -> Self {
+        self.push(|cmd| cmd.get_low_data_bits())
+    }
+
+    pub fn gpio_read_high(self) -> Self {
+        self.push(|cmd| cmd.get_high_data_bits())
+    }
+
+    pub fn spi_write(self, data: &[u8]) -> Self {
+        let (edge, order) = (self.mode.out_edge(), self.order);
+        self.push(|cmd| cmd.clock_bytes_out(edge, order, data))
+    }
+
+    pub fn spi_read(self, len: usize) -> Self {
+        let (edge, order) = (self.mode.in_edge(), self.order);
+        self.push(|cmd| cmd.clock_bytes_in(edge, order, len))
+    }
+
+    pub fn spi_transfer(self, data: &[u8]) -> Self {
+        let (edge, order) = (self.mode.out_edge(), self.order);
+        self.push(|cmd| cmd.clock_bytes_inout(edge, order, data))
+    }
+
+    pub fn wait_on_io(self, high: bool) -> Self {
+        self.push(|cmd| match high {
+            true => cmd.wait_on_io_high(),
+            false => cmd.wait_on_io_low(),
+        })
+    }
+
+    pub fn delay(self, duration: Duration) -> Self {
+        let cycles = (duration.as_nanos() * self.frequency as u128).div_ceil(1_000_000_000);
+        self.push(|cmd| cmd.delay_clocks(cycles as usize))
+    }
+
+    pub fn len(&self) -> usize {
+        self.reads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reads.is_empty()
+    }
+
+    pub async fn commit(self, interface: &(impl MpsseInterface + Sync)) -> Result<Vec<Vec<u8>>> {
+        let res = interface.execute(self.cmd).await?;
+        if res.len() != self.reads.iter().sum::<usize>() {
+            return Err(Error::InvalidResponse(res));
+        }
+
+        let mut rest = &res[..];
+        let results = self
+            .reads
+            .iter()
+            .map(|&len| {
+                let (op, tail) = rest.split_at(len);
+                rest = tail;
+                op.to_vec()
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    fn push(mut self, op: impl FnOnce(MpsseCmdBuilder) -> MpsseCmdBuilder) -> Self {
+        let before = self.cmd.read_len();
+        self.cmd = op(self.cmd);
+        self.reads.push(self.cmd.read_len() - before);
+        self
+    }
+}
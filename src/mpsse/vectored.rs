@@ -0,0 +1,137 @@
+use std::io::IoSlice;
+
+use super::{
+    command_responses, parse_response, response_segments, shift_opcode, BitOrder, ClockEdge,
+    MpsseCmdBuilder, SendImmediate, MAX_CLOCK_BYTES,
+};
+use crate::{Interface, Result};
+
+#[derive(Clone, Debug)]
+enum Part<'a> {
+    Command(Vec<u8>),
+    Clock([u8; 3], &'a [u8], bool),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VectoredCmdBuilder<'a> {
+    parts: Vec<Part<'a>>,
+    read_len: usize,
+}
+
+impl<'a> VectoredCmdBuilder<'a> {
+    pub fn new() -> Self {
+        VectoredCmdBuilder {
+            parts: Vec::new(),
+            read_len: 0,
+        }
+    }
+
+    pub fn read_len(&self) -> usize {
+        self.read_len
+    }
+
+    pub fn len(&self) -> usize {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Command(bytes) => bytes.len(),
+                Part::Clock(header, data, _) => header.len() + data.len(),
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn command(mut self, cmd: MpsseCmdBuilder) -> Self {
+        self.read_len += cmd.read_len();
+        let bytes = cmd.into_bytes();
+        match self.parts.last_mut() {
+            Some(Part::Command(last)) => last.extend_from_slice(&bytes),
+            _ => self.parts.push(Part::Command(bytes)),
+        }
+        self
+    }
+
+    pub fn clock_bytes_out(self, edge: ClockEdge, order: BitOrder, data: &'a [u8]) -> Self {
+        let op = shift_opcode(Some(edge), None, order, false);
+        self.clock_bytes(op, data, false)
+    }
+
+    pub fn clock_bytes_inout(self, out_edge: ClockEdge, order: BitOrder, data: &'a [u8]) -> Self {
+        let op = shift_opcode(Some(out_edge), Some(out_edge.opposite()), order, false);
+        let mut builder = self.clock_bytes(op, data, true);
+        builder.read_len += data.len();
+        builder
+    }
+
+    pub async fn send(self, interface: &Interface) -> Result<Vec<u8>> {
+        let mut steps = Vec::new();
+        let mut pieces: Vec<&[u8]> = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Command(bytes) => {
+                    steps.extend(command_responses(bytes, interface.device_type));
+                    pieces.push(bytes);
+                }
+                Part::Clock(header, data, read) => {
+                    let len = if *read { data.len() } else { 0 };
+                    steps.push((Ok(len), header.len() + data.len()));
+                    pieces.extend([&header[..], *data]);
+                }
+            }
+        }
+
+        let segments = response_segments(&steps);
+        let len = segments.iter().map(|&(_, len)| len).sum();
+        let mut res = vec![0u8; len];
+        let immediate = [SendImmediate::byte()];
+
+        let _guard = interface.command_lock.lock().await;
+        interface.require_bitmode(crate::Bitmode::Mpsse)?;
+
+        let (mut cmd_pos, mut res_pos) = (0, 0);
+        for (cmd_end, res_len) in segments {
+            let mut slices = io_slices(&pieces, cmd_pos, cmd_end);
+            if res_len > 0 {
+                slices.push(IoSlice::new(&immediate));
+            }
+
+            interface.write_vectored(&slices).await?;
+            interface
+                .read_all(&mut res[res_pos..res_pos + res_len])
+                .await?;
+
+            cmd_pos = cmd_end;
+            res_pos += res_len;
+        }
+
+        parse_response(&steps, res)
+    }
+
+    fn clock_bytes(mut self, op: u8, data: &'a [u8], read: bool) -> Self {
+        for chunk in data.chunks(MAX_CLOCK_BYTES) {
+            let len = ((chunk.len() - 1) as u16).to_le_bytes();
+            self.parts
+                .push(Part::Clock([op, len[0], len[1]], chunk, read));
+        }
+
+        self
+    }
+}
+
+fn io_slices<'b>(pieces: &[&'b [u8]], start: usize, end: usize) -> Vec<IoSlice<'b>> {
+    let mut slices = Vec::new();
+    let mut pos = 0;
+
+    for piece in pieces {
+        let (from, to) = (start.max(pos), end.min(pos + piece.len()));
+        if from < to {
+            slices.push(IoSlice::new(&piece[from - pos..to - pos]));
+        }
+        pos += piece.len();
+    }
+
+    slices
+}
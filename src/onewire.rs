@@ -0,0 +1,290 @@
+use futures_core::Stream;
+
+use crate::bitbang::SyncBitbang;
+use crate::{Error, Interface, Result};
+
+const RESET_BAUDRATE: u32 = 9600;
+const DATA_BAUDRATE: u32 = 115_200;
+const RESET_PULSE: u8 = 0xf0;
+const UART_ONE: u8 = 0xff;
+const UART_ZERO: u8 = 0x00;
+
+const BITBANG_SAMPLE_RATE: u32 = 1_000_000;
+const RESET_LOW_US: usize = 480;
+const PRESENCE_WINDOW_US: core::ops::Range<usize> = 60..240;
+const SLOT_US: usize = 70;
+const WRITE_ONE_LOW_US: usize = 6;
+const WRITE_ZERO_LOW_US: usize = 60;
+const READ_SAMPLE_US: usize = 15;
+
+const SEARCH_ROM: u8 = 0xf0;
+const MATCH_ROM: u8 = 0x55;
+const SKIP_ROM: u8 = 0xcc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RomCode(pub [u8; 8]);
+
+impl RomCode {
+    pub fn family(&self) -> u8 {
+        self.0[0]
+    }
+
+    pub fn serial(&self) -> [u8; 6] {
+        [
+            self.0[1], self.0[2], self.0[3], self.0[4], self.0[5], self.0[6],
+        ]
+    }
+
+    pub fn crc_valid(&self) -> bool {
+        crc8(&self.0[..7]) == self.0[7]
+    }
+}
+
+#[derive(Debug)]
+enum Backend {
+    Uart(Interface),
+    Bitbang {
+        bitbang: SyncBitbang,
+        tx: u8,
+        rx: u8,
+    },
+}
+
+#[derive(Debug)]
+pub struct OneWire {
+    backend: Backend,
+}
+
+#[derive(Debug, Default)]
+struct SearchState {
+    rom: [u8; 8],
+    last_discrepancy: usize,
+    done: bool,
+}
+
+impl OneWire {
+    pub async fn uart(interface: Interface) -> Result<Self> {
+        interface.set_baudrate(DATA_BAUDRATE).await?;
+        interface.purge_all().await?;
+
+        Ok(OneWire {
+            backend: Backend::Uart(interface),
+        })
+    }
+
+    pub async fn bitbang(interface: Interface, tx: u8, rx: u8) -> Result<Self> {
+        if tx > 7 || rx > 7 || tx == rx {
+            return Err(Error::InvalidParameter(format!(
+                "1-wire bitbang needs distinct pins in 0..=7, got tx {} rx {}",
+                tx, rx
+            )));
+        }
+
+        let mut bitbang = SyncBitbang::enter(interface, 1 << tx, BITBANG_SAMPLE_RATE).await?;
+        bitbang.set_output(1 << tx);
+
+        Ok(OneWire {
+            backend: Backend::Bitbang {
+                bitbang,
+                tx: 1 << tx,
+                rx: 1 << rx,
+            },
+        })
+    }
+
+    pub async fn into_inner(self) -> Result<Interface> {
+        match self.backend {
+            Backend::Uart(interface) => Ok(interface),
+            Backend::Bitbang { bitbang, .. } => bitbang.exit().await,
+        }
+    }
+
+    pub async fn reset(&mut self) -> Result<bool> {
+        match &self.backend {
+            Backend::Uart(interface) => {
+                interface.set_baudrate(RESET_BAUDRATE).await?;
+                interface.write_all(vec![RESET_PULSE]).await?;
+                let mut echo = [0u8];
+                interface.read_all(&mut echo).await?;
+                interface.set_baudrate(DATA_BAUDRATE).await?;
+
+                Ok(echo[0] != RESET_PULSE)
+            }
+            Backend::Bitbang { bitbang, tx, rx } => {
+                let mut wave = vec![0u8; RESET_LOW_US];
+                wave.resize(RESET_LOW_US * 2, *tx);
+                let samples = bitbang.transfer(&wave).await?;
+
+                Ok(samples[RESET_LOW_US..][PRESENCE_WINDOW_US]
+                    .iter()
+                    .any(|sample| sample & rx == 0))
+            }
+        }
+    }
+
+    pub async fn touch_bits(&mut self, bits: &[bool]) -> Result<Vec<bool>> {
+        match &self.backend {
+            Backend::Uart(interface) => {
+                let out: Vec<u8> = bits
+                    .iter()
+                    .map(|&bit| if bit { UART_ONE } else { UART_ZERO })
+                    .collect();
+                let mut echo = vec![0u8; out.len()];
+                interface.write_all(out).await?;
+                interface.read_all(&mut echo).await?;
+
+                Ok(echo.iter().map(|&b| b == UART_ONE).collect())
+            }
+            Backend::Bitbang { bitbang, tx, rx } => {
+                let mut wave = Vec::with_capacity(bits.len() * SLOT_US);
+                for &bit in bits {
+                    let low = if bit {
+                        WRITE_ONE_LOW_US
+                    } else {
+                        WRITE_ZERO_LOW_US
+                    };
+                    wave.extend((0..SLOT_US).map(|us| if us < low { 0 } else { *tx }));
+                }
+                let samples = bitbang.transfer(&wave).await?;
+
+                Ok(samples
+                    .chunks(SLOT_US)
+                    .map(|slot| slot[READ_SAMPLE_US] & rx != 0)
+                    .collect())
+            }
+        }
+    }
+
+    pub async fn write_bit(&mut self, bit: bool) -> Result<()> {
+        self.touch_bits(&[bit]).await?;
+
+        Ok(())
+    }
+
+    pub async fn read_bit(&mut self) -> Result<bool> {
+        Ok(self.touch_bits(&[true]).await?[0])
+    }
+
+    pub async fn write_bytes(&mut self, data: &[u8]) -> Result<()> {
+        self.touch_bits(&to_bits(data)).await?;
+
+        Ok(())
+    }
+
+    pub async fn read_bytes(&mut self, buf: &mut [u8]) -> Result<()> {
+        let bits = self.touch_bits(&vec![true; buf.len() * 8]).await?;
+        buf.copy_from_slice(&from_bits(&bits));
+
+        Ok(())
+    }
+
+    pub async fn select(&mut self, rom: RomCode) -> Result<()> {
+        let mut cmd = vec![MATCH_ROM];
+        cmd.extend_from_slice(&rom.0);
+
+        self.write_bytes(&cmd).await
+    }
+
+    pub async fn skip_rom(&mut self) -> Result<()> {
+        self.write_bytes(&[SKIP_ROM]).await
+    }
+
+    pub fn search(&mut self) -> impl Stream<Item = Result<RomCode>> + '_ {
+        futures_lite::stream::unfold(
+            (self, SearchState::default()),
+            |(bus, mut state)| async move {
+                if state.done {
+                    return None;
+                }
+
+                match bus.search_next(&mut state).await {
+                    Ok(Some(rom)) => Some((Ok(rom), (bus, state))),
+                    Ok(None) => None,
+                    Err(err) => {
+                        state.done = true;
+                        Some((Err(err), (bus, state)))
+                    }
+                }
+            },
+        )
+    }
+
+    async fn search_next(&mut self, state: &mut SearchState) -> Result<Option<RomCode>> {
+        if !self.reset().await? {
+            return Ok(None);
+        }
+        self.write_bytes(&[SEARCH_ROM]).await?;
+
+        let mut last_zero = 0;
+        for index in 1..=64 {
+            let (byte, mask) = ((index - 1) / 8, 1 << ((index - 1) % 8));
+
+            let read = self.touch_bits(&[true, true]).await?;
+            let direction = match (read[0], read[1]) {
+                (true, true) => return Ok(None),
+                (bit, complement) if bit != complement => bit,
+                _ => {
+                    let direction = if index < state.last_discrepancy {
+                        state.rom[byte] & mask != 0
+                    } else {
+                        index == state.last_discrepancy
+                    };
+                    if !direction {
+                        last_zero = index;
+                    }
+                    direction
+                }
+            };
+
+            if direction {
+                state.rom[byte] |= mask;
+            } else {
+                state.rom[byte] &= !mask;
+            }
+            self.write_bit(direction).await?;
+        }
+
+        state.last_discrepancy = last_zero;
+        state.done = last_zero == 0;
+
+        let rom = RomCode(state.rom);
+        if !rom.crc_valid() {
+            return Err(Error::InvalidResponse(rom.0.to_vec()));
+        }
+
+        Ok(Some(rom))
+    }
+}
+
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |crc, &byte| {
+        (0..8)
+            .fold((crc, byte), |(crc, byte), _| {
+                let mix = (crc ^ byte) & 0x01;
+                let crc = if mix != 0 {
+                    (crc >> 1) ^ 0x8c
+                } else {
+                    crc >> 1
+                };
+                (crc, byte >> 1)
+            })
+            .0
+    })
+}
+
+fn to_bits(data: &[u8]) -> Vec<bool> {
+    data.iter()
+        .flat_map(|&byte| (0..8).map(move |bit| byte & (1 << bit) != 0))
+        .collect()
+}
+
+fn from_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << i))
+        })
+        .collect()
+}
@@ -0,0 +1,135 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Packet<'a> {
+    pub status: [u8; 2],
+    pub data: &'a [u8],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FtdiPacketDecoder {
+    max_packet_size: usize,
+}
+
+impl FtdiPacketDecoder {
+    pub fn new(max_packet_size: usize) -> Self {
+        FtdiPacketDecoder { max_packet_size }
+    }
+
+    pub fn max_packet_size(&self) -> usize {
+        self.max_packet_size
+    }
+
+    pub fn packets<'a>(&self, buffer: &'a [u8]) -> impl Iterator<Item = Packet<'a>> {
+        buffer
+            .chunks(self.max_packet_size)
+            .filter(|chunk| chunk.len() >= 2)
+            .map(|chunk| Packet {
+                status: [chunk[0], chunk[1]],
+                data: &chunk[2..],
+            })
+    }
+
+    pub fn payload<'a>(&self, buffer: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+        self.packets(buffer)
+            .map(|packet| packet.data)
+            .filter(|data| !data.is_empty())
+    }
+
+    pub fn payload_len(&self, buffer: &[u8]) -> usize {
+        self.payload(buffer).map(<[u8]>::len).sum()
+    }
+
+    pub fn decode_into(&self, buffer: &[u8], out: &mut impl Extend<u8>) {
+        for data in self.payload(buffer) {
+            out.extend(data.iter().copied());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packetize(max_packet_size: usize, data: &[u8]) -> Vec<u8> {
+        data.chunks(max_packet_size - 2)
+            .flat_map(|chunk| [0x01, 0x60].iter().chain(chunk))
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn short_packet() {
+        let decoder = FtdiPacketDecoder::new(64);
+        let buffer = [0x01, 0x60, 0xaa, 0xbb, 0xcc];
+
+        let packets: Vec<_> = decoder.packets(&buffer).collect();
+        assert_eq!(
+            packets,
+            [Packet {
+                status: [0x01, 0x60],
+                data: &[0xaa, 0xbb, 0xcc],
+            }]
+        );
+        assert_eq!(decoder.payload_len(&buffer), 3);
+    }
+
+    #[test]
+    fn exact_multiple() {
+        let decoder = FtdiPacketDecoder::new(64);
+        let data: Vec<u8> = (0..124).collect();
+        let buffer = packetize(64, &data);
+        assert_eq!(buffer.len(), 128);
+
+        let mut out = Vec::new();
+        decoder.decode_into(&buffer, &mut out);
+        assert_eq!(decoder.packets(&buffer).count(), 2);
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn full_and_high_speed_packet_sizes() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        for max_packet_size in [64, 512] {
+            let decoder = FtdiPacketDecoder::new(max_packet_size);
+            let buffer = packetize(max_packet_size, &data);
+
+            let mut out = Vec::new();
+            decoder.decode_into(&buffer, &mut out);
+            assert_eq!(
+                decoder.packets(&buffer).count(),
+                data.len().div_ceil(max_packet_size - 2)
+            );
+            assert_eq!(out, data);
+        }
+
+        let high_speed = packetize(512, &data);
+        let mut misread = Vec::new();
+        FtdiPacketDecoder::new(64).decode_into(&high_speed, &mut misread);
+        assert_ne!(misread, data);
+    }
+
+    #[test]
+    fn zero_length_packets() {
+        let decoder = FtdiPacketDecoder::new(64);
+
+        assert_eq!(decoder.packets(&[]).count(), 0);
+        assert_eq!(decoder.payload_len(&[0x01]), 0);
+
+        let buffer = [0x01, 0x60];
+        assert_eq!(decoder.packets(&buffer).count(), 1);
+        assert_eq!(decoder.payload(&buffer).count(), 0);
+        assert_eq!(decoder.payload_len(&buffer), 0);
+    }
+
+    #[test]
+    fn trailing_status_only_packet() {
+        let decoder = FtdiPacketDecoder::new(4);
+        let buffer = [0x01, 0x60, 0xaa, 0xbb, 0x01, 0x62, 0xcc, 0xdd, 0x01, 0x60];
+
+        let statuses: Vec<_> = decoder.packets(&buffer).map(|p| p.status).collect();
+        assert_eq!(statuses, [[0x01, 0x60], [0x01, 0x62], [0x01, 0x60]]);
+
+        let payload: Vec<_> = decoder.payload(&buffer).collect();
+        assert_eq!(payload, [&[0xaa, 0xbb][..], &[0xcc, 0xdd][..]]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_lock::{Mutex, MutexGuard};
+
+use crate::gpio::Bank;
+use crate::{Error, Interface, Result};
+
+const PIN_COUNT: usize = 16;
+
+#[derive(Debug, Default)]
+pub(crate) struct PinRegistry {
+    owners: std::sync::Mutex<[Option<(u64, &'static str)>; PIN_COUNT]>,
+    pub(crate) state: Mutex<[(u8, u8); 2]>,
+    next_id: AtomicU64,
+}
+
+impl PinRegistry {
+    pub(crate) fn claim(self: &Arc<Self>, owner: &'static str) -> PinClaim {
+        PinClaim {
+            registry: self.clone(),
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            owner,
+        }
+    }
+
+    pub(crate) fn owner(&self, pin: u8) -> Option<&'static str> {
+        let owners = self.owners.lock().unwrap();
+        owners.get(pin as usize)?.map(|(_, owner)| owner)
+    }
+}
+
+#[derive(Debug)]
+pub struct PinClaim {
+    registry: Arc<PinRegistry>,
+    id: u64,
+    owner: &'static str,
+}
+
+impl PinClaim {
+    pub fn owner(&self) -> &'static str {
+        self.owner
+    }
+
+    pub fn mask(&self) -> u16 {
+        let owners = self.registry.owners.lock().unwrap();
+
+        (0..PIN_COUNT)
+            .filter(|&pin| matches!(owners[pin], Some((id, _)) if id == self.id))
+            .fold(0, |mask, pin| mask | (1 << pin))
+    }
+
+    pub fn extend(&self, mask: u16) -> Result<()> {
+        let mut owners = self.registry.owners.lock().unwrap();
+
+        for pin in (0..PIN_COUNT).filter(|pin| mask & (1 << pin) != 0) {
+            if let Some((id, owner)) = owners[pin] {
+                if id != self.id {
+                    return Err(Error::PinConflict {
+                        pin: pin as u8,
+                        owner,
+                    });
+                }
+            }
+        }
+
+        for pin in (0..PIN_COUNT).filter(|pin| mask & (1 << pin) != 0) {
+            owners[pin] = Some((self.id, self.owner));
+        }
+
+        Ok(())
+    }
+
+    pub async fn write(
+        &self,
+        interface: &Interface,
+        bank: Bank,
+        update: impl FnOnce(&mut u8, &mut u8),
+    ) -> Result<(u8, u8)> {
+        let mut pins = self.bank(bank).await;
+        let (mut value, mut direction) = pins.state[bank as usize];
+        update(&mut value, &mut direction);
+
+        let (value, direction) = pins.merge(value, direction);
+        interface.write_data_bits(bank, value, direction).await?;
+        pins.state[bank as usize] = (value, direction);
+
+        Ok((value, direction))
+    }
+
+    pub(crate) async fn bank(&self, bank: Bank) -> PinBank<'_> {
+        PinBank {
+            state: self.registry.state.lock().await,
+            bank,
+            mask: self.mask(),
+        }
+    }
+}
+
+pub(crate) struct PinBank<'a> {
+    state: MutexGuard<'a, [(u8, u8); 2]>,
+    bank: Bank,
+    mask: u16,
+}
+
+impl PinBank<'_> {
+    pub(crate) fn select(&mut self, bank: Bank) -> &mut Self {
+        self.bank = bank;
+        self
+    }
+
+    pub(crate) fn others(&self) -> (u8, u8) {
+        let (value, direction) = self.state[self.bank as usize];
+
+        (value & !self.owned(), direction & !self.owned())
+    }
+
+    pub(crate) fn compose(&mut self, value: u8, direction: u8) -> (u8, u8) {
+        let composed = self.merge(value, direction);
+        self.state[self.bank as usize] = composed;
+
+        composed
+    }
+
+    fn merge(&self, value: u8, direction: u8) -> (u8, u8) {
+        let (other_value, other_direction) = self.others();
+
+        (
+            other_value | (value & self.owned()),
+            other_direction | (direction & self.owned()),
+        )
+    }
+
+    fn owned(&self) -> u8 {
+        (self.mask >> (self.bank as u16 * 8)) as u8
+    }
+}
+
+impl Drop for PinClaim {
+    fn drop(&mut self) {
+        let mut owners = self.registry.owners.lock().unwrap();
+
+        for owner in owners.iter_mut() {
+            if matches!(owner, Some((id, _)) if *id == self.id) {
+                *owner = None;
+            }
+        }
+    }
+}
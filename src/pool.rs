@@ -0,0 +1,32 @@
+use nusb::transfer::Buffer;
+
+use crate::transport::BulkEndpoint;
+
+#[derive(Debug, Default)]
+pub(crate) struct BufferPool {
+    buffers: std::sync::Mutex<Vec<Buffer>>,
+}
+
+impl BufferPool {
+    pub(crate) fn take(&self, ep_in: &mut dyn BulkEndpoint, len: usize) -> Buffer {
+        let mut buffers = self.buffers.lock().unwrap();
+
+        while let Some(mut buffer) = buffers.pop() {
+            if buffer.capacity() >= len {
+                buffer.clear();
+                buffer.set_requested_len(len);
+                return buffer;
+            }
+        }
+
+        ep_in.allocate(len)
+    }
+
+    pub(crate) fn put(&self, buffer: Buffer, limit: usize) {
+        let mut buffers = self.buffers.lock().unwrap();
+
+        if buffers.len() < limit {
+            buffers.push(buffer);
+        }
+    }
+}
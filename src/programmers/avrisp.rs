@@ -0,0 +1,216 @@
+use std::time::{Duration, Instant};
+
+use crate::gpio::{Direction, Pin};
+use crate::spi::SpiBus;
+use crate::{Error, Result};
+
+const PROGRAMMING_ENABLE: [u8; 4] = [0xac, 0x53, 0x00, 0x00];
+const CHIP_ERASE: [u8; 4] = [0xac, 0x80, 0x00, 0x00];
+const POLL_READY: [u8; 4] = [0xf0, 0x00, 0x00, 0x00];
+
+const READ_SIGNATURE: u8 = 0x30;
+const READ_FLASH_LOW: u8 = 0x20;
+const READ_FLASH_HIGH: u8 = 0x28;
+const LOAD_FLASH_LOW: u8 = 0x40;
+const LOAD_FLASH_HIGH: u8 = 0x48;
+const WRITE_FLASH_PAGE: u8 = 0x4c;
+const READ_EEPROM: u8 = 0xa0;
+const WRITE_EEPROM: u8 = 0xc0;
+
+const ENABLE_ATTEMPTS: usize = 4;
+const RESET_SETTLE: Duration = Duration::from_millis(20);
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+const WRITE_TIMEOUT: Duration = Duration::from_millis(50);
+const ERASE_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fuse {
+    Low,
+    High,
+    Extended,
+    Lock,
+}
+
+impl Fuse {
+    fn read_command(self) -> [u8; 4] {
+        match self {
+            Fuse::Low => [0x50, 0x00, 0x00, 0x00],
+            Fuse::High => [0x58, 0x08, 0x00, 0x00],
+            Fuse::Extended => [0x50, 0x08, 0x00, 0x00],
+            Fuse::Lock => [0x58, 0x00, 0x00, 0x00],
+        }
+    }
+
+    fn write_command(self, value: u8) -> [u8; 4] {
+        let op = match self {
+            Fuse::Low => 0xa0,
+            Fuse::High => 0xa8,
+            Fuse::Extended => 0xa4,
+            Fuse::Lock => 0xe0,
+        };
+
+        [0xac, op, 0x00, value]
+    }
+}
+
+#[derive(Debug)]
+pub struct AvrIsp {
+    spi: SpiBus,
+    reset: Pin,
+}
+
+impl AvrIsp {
+    pub fn new(spi: SpiBus, reset: Pin) -> Self {
+        AvrIsp { spi, reset }
+    }
+
+    pub fn into_inner(self) -> (SpiBus, Pin) {
+        (self.spi, self.reset)
+    }
+
+    pub async fn enter_programming_mode(&self) -> Result<()> {
+        self.reset.set_direction(Direction::Output).await?;
+
+        for _ in 0..ENABLE_ATTEMPTS {
+            self.reset.set_level(true).await?;
+            self.reset.set_level(false).await?;
+            futures_timer::Delay::new(RESET_SETTLE).await;
+
+            let mut buf = PROGRAMMING_ENABLE;
+            self.spi.transfer(&mut buf).await?;
+            if buf[2] == PROGRAMMING_ENABLE[1] {
+                return Ok(());
+            }
+        }
+
+        Err(Error::AvrIspSync)
+    }
+
+    pub async fn leave_programming_mode(&self) -> Result<()> {
+        self.reset.set_level(true).await
+    }
+
+    pub async fn read_signature(&self) -> Result<[u8; 3]> {
+        let res = self
+            .batch((0..3).map(|i| [READ_SIGNATURE, 0x00, i, 0x00]))
+            .await?;
+
+        Ok([res[0], res[1], res[2]])
+    }
+
+    pub async fn chip_erase(&self) -> Result<()> {
+        self.spi.write(&CHIP_ERASE).await?;
+        self.wait_ready(ERASE_TIMEOUT).await
+    }
+
+    pub async fn read_fuse(&self, fuse: Fuse) -> Result<u8> {
+        let mut buf = fuse.read_command();
+        self.spi.transfer(&mut buf).await?;
+
+        Ok(buf[3])
+    }
+
+    pub async fn write_fuse(&self, fuse: Fuse, value: u8) -> Result<()> {
+        self.spi.write(&fuse.write_command(value)).await?;
+        self.wait_ready(WRITE_TIMEOUT).await
+    }
+
+    pub async fn read_flash(&self, address: u32, buf: &mut [u8]) -> Result<()> {
+        let res = self
+            .batch((0..buf.len() as u32).map(|i| {
+                let byte = address + i;
+                let [_, _, hi, lo] = (byte / 2).to_be_bytes();
+                let op = if byte % 2 == 0 {
+                    READ_FLASH_LOW
+                } else {
+                    READ_FLASH_HIGH
+                };
+                [op, hi, lo, 0x00]
+            }))
+            .await?;
+        buf.copy_from_slice(&res);
+
+        Ok(())
+    }
+
+    pub async fn write_flash(&self, address: u32, data: &[u8], page_size: usize) -> Result<()> {
+        if page_size == 0 || address as usize % page_size != 0 {
+            return Err(Error::InvalidParameter(format!(
+                "flash address {:#x} is not aligned to the {} byte page size",
+                address, page_size
+            )));
+        }
+
+        for (i, page) in data.chunks(page_size).enumerate() {
+            let start = address + (i * page_size) as u32;
+
+            let mut cmd = Vec::with_capacity(page.len() * 4);
+            for (offset, &byte) in page.iter().enumerate() {
+                let op = if offset % 2 == 0 {
+                    LOAD_FLASH_LOW
+                } else {
+                    LOAD_FLASH_HIGH
+                };
+                cmd.extend_from_slice(&[op, 0x00, (offset / 2) as u8, byte]);
+            }
+
+            let [_, _, hi, lo] = (start / 2).to_be_bytes();
+            cmd.extend_from_slice(&[WRITE_FLASH_PAGE, hi, lo, 0x00]);
+
+            self.spi.write(&cmd).await?;
+            self.wait_ready(WRITE_TIMEOUT).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn read_eeprom(&self, address: u16, buf: &mut [u8]) -> Result<()> {
+        let res = self
+            .batch((0..buf.len() as u16).map(|i| {
+                let [hi, lo] = (address + i).to_be_bytes();
+                [READ_EEPROM, hi, lo, 0x00]
+            }))
+            .await?;
+        buf.copy_from_slice(&res);
+
+        Ok(())
+    }
+
+    pub async fn write_eeprom(&self, address: u16, data: &[u8]) -> Result<()> {
+        for (i, &byte) in data.iter().enumerate() {
+            let [hi, lo] = (address + i as u16).to_be_bytes();
+            self.spi.write(&[WRITE_EEPROM, hi, lo, byte]).await?;
+            self.wait_ready(WRITE_TIMEOUT).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn wait_ready(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let mut buf = POLL_READY;
+            self.spi.transfer(&mut buf).await?;
+            if buf[3] & 0x01 == 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout {
+                    operation: "avr wait_ready",
+                    requested: 0,
+                    completed: 0,
+                });
+            }
+
+            futures_timer::Delay::new(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn batch(&self, commands: impl Iterator<Item = [u8; 4]>) -> Result<Vec<u8>> {
+        let mut buf: Vec<u8> = commands.flatten().collect();
+        self.spi.transfer(&mut buf).await?;
+
+        Ok(buf.chunks(4).map(|res| res[3]).collect())
+    }
+}
@@ -0,0 +1,86 @@
+use crate::DeviceType;
+
+pub const RESET_SIO: u16 = 0;
+pub const RESET_PURGE_RX: u16 = 1;
+pub const RESET_PURGE_TX: u16 = 2;
+
+pub const MODEM_DTR: u16 = 0x0001;
+pub const MODEM_RTS: u16 = 0x0002;
+
+pub const BAUD_HIGH_SPEED_CLOCK: u32 = 0x20000;
+pub const BAUD_INDEX_HIGH_MASK: u32 = 0xff00;
+
+pub fn channel_index(num: u8) -> u16 {
+    num as u16 + 1
+}
+
+pub fn endpoint_in(num: u8) -> u8 {
+    ((num + 1) * 2 - 1) | 0x80
+}
+
+pub fn endpoint_out(num: u8) -> u8 {
+    (num + 1) * 2
+}
+
+pub fn modem_control(line: u16, high: bool) -> u16 {
+    (line << 8) | if high { line } else { 0 }
+}
+
+pub fn baudrate_index(device_type: DeviceType, encoded: u32, num: u8) -> u16 {
+    match device_type {
+        DeviceType::FT232R | DeviceType::FtX => (encoded >> 16) as u16,
+        _ => ((encoded >> 8) & BAUD_INDEX_HIGH_MASK) as u16 | channel_index(num),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_numbers() {
+        for (num, index, ep_in, ep_out) in [
+            (0, 1, 0x81, 0x02),
+            (1, 2, 0x83, 0x04),
+            (2, 3, 0x85, 0x06),
+            (3, 4, 0x87, 0x08),
+        ] {
+            assert_eq!(channel_index(num), index);
+            assert_eq!(endpoint_in(num), ep_in);
+            assert_eq!(endpoint_out(num), ep_out);
+        }
+    }
+
+    #[test]
+    fn modem_control_masks() {
+        assert_eq!(modem_control(MODEM_DTR, true), 0x0101);
+        assert_eq!(modem_control(MODEM_DTR, false), 0x0100);
+        assert_eq!(modem_control(MODEM_RTS, true), 0x0202);
+        assert_eq!(modem_control(MODEM_RTS, false), 0x0200);
+        assert_eq!(modem_control(MODEM_DTR | MODEM_RTS, true), 0x0303);
+    }
+
+    #[test]
+    fn baudrate_index_single_channel() {
+        for device_type in [DeviceType::FT232R, DeviceType::FtX] {
+            assert_eq!(baudrate_index(device_type, 0x0000_4138, 0), 0x0000);
+            assert_eq!(baudrate_index(device_type, 0x0001_c000, 0), 0x0001);
+        }
+    }
+
+    #[test]
+    fn baudrate_index_multi_channel() {
+        let encoded = 0x0002_c068 | BAUD_HIGH_SPEED_CLOCK;
+
+        for (num, index) in [(0, 0x0201), (1, 0x0202)] {
+            assert_eq!(baudrate_index(DeviceType::FT2232H, encoded, num), index);
+        }
+
+        for (num, index) in [(0, 0x0201), (1, 0x0202), (2, 0x0203), (3, 0x0204)] {
+            assert_eq!(baudrate_index(DeviceType::FT4232H, encoded, num), index);
+        }
+
+        assert_eq!(baudrate_index(DeviceType::FT2232C, 0x0001_4138, 1), 0x0102);
+        assert_eq!(baudrate_index(DeviceType::FT232H, 0x0000_4138, 0), 0x0001);
+    }
+}
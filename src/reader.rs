@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_lock::Mutex;
+use event_listener::Event;
+use futures_lite::future;
+
+use nusb::transfer::TransferError;
+
+use crate::line::LineMonitor;
+use crate::pool::BufferPool;
+use crate::stats::Counters;
+use crate::transport::BulkEndpoint;
+use crate::{FtdiPacketDecoder, Interface, Result};
+
+#[derive(Debug)]
+struct Ring {
+    data: VecDeque<u8>,
+    capacity: usize,
+    error: Option<TransferError>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    ring: std::sync::Mutex<Ring>,
+    data_ready: Event,
+    space_ready: Event,
+    shutdown: Event,
+    stopping: AtomicBool,
+    finished: AtomicBool,
+    line_monitor: Arc<LineMonitor>,
+    counters: Arc<Counters>,
+    connection: Arc<crate::Connection>,
+}
+
+#[derive(Debug)]
+pub(crate) struct BackgroundReader {
+    shared: Arc<Shared>,
+}
+
+impl BackgroundReader {
+    pub(crate) fn spawn(interface: &Interface, capacity: usize, initial: &[u8]) -> Self {
+        let ep_in = interface.ep_in.clone();
+        let decoder = interface.packet_decoder();
+        let transfer_size = interface.read_transfer_size();
+        let queue_depth = interface.read_queue_depth();
+
+        let shared = Arc::new(Shared {
+            ring: std::sync::Mutex::new(Ring {
+                data: initial.iter().copied().collect(),
+                capacity: capacity.max(transfer_size * queue_depth),
+                error: None,
+            }),
+            data_ready: Event::new(),
+            space_ready: Event::new(),
+            shutdown: Event::new(),
+            stopping: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            line_monitor: interface.line_monitor.clone(),
+            counters: interface.counters.clone(),
+            connection: interface.connection.clone(),
+        });
+
+        let task = shared.clone();
+        std::thread::spawn(move || {
+            future::block_on(run(
+                task.clone(),
+                ep_in,
+                decoder,
+                transfer_size,
+                queue_depth,
+            ));
+            task.finished.store(true, Ordering::Release);
+            task.data_ready.notify(usize::MAX);
+        });
+
+        BackgroundReader { shared }
+    }
+
+    pub(crate) fn buffered(&self) -> usize {
+        self.shared.ring.lock().unwrap().data.len()
+    }
+
+    pub(crate) fn peek(&self, buf: &mut [u8]) -> usize {
+        let ring = self.shared.ring.lock().unwrap();
+        let n = ring.data.len().min(buf.len());
+        for (dst, src) in buf.iter_mut().zip(ring.data.iter()) {
+            *dst = *src;
+        }
+        n
+    }
+
+    pub(crate) fn unread(&self, data: &[u8]) {
+        let mut ring = self.shared.ring.lock().unwrap();
+        for &byte in data.iter().rev() {
+            ring.data.push_front(byte);
+        }
+        self.shared.data_ready.notify(usize::MAX);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.shared.ring.lock().unwrap().data.clear();
+        self.shared.space_ready.notify(usize::MAX);
+    }
+
+    pub(crate) async fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let listener = self.shared.data_ready.listen();
+
+            {
+                let mut ring = self.shared.ring.lock().unwrap();
+
+                if !ring.data.is_empty() {
+                    let n = ring.data.len().min(buf.len());
+                    for (dst, src) in buf.iter_mut().zip(ring.data.drain(..n)) {
+                        *dst = src;
+                    }
+                    self.shared.space_ready.notify(usize::MAX);
+
+                    return Ok(n);
+                }
+
+                if let Some(err) = ring.error {
+                    return Err(err.into());
+                }
+            }
+
+            listener.await;
+        }
+    }
+
+    pub(crate) async fn stop(&self) -> Vec<u8> {
+        self.shared.request_shutdown();
+
+        loop {
+            let listener = self.shared.data_ready.listen();
+            if self.shared.finished.load(Ordering::Acquire) {
+                break;
+            }
+            listener.await;
+        }
+
+        self.shared.ring.lock().unwrap().data.drain(..).collect()
+    }
+}
+
+impl Drop for BackgroundReader {
+    fn drop(&mut self) {
+        self.shared.request_shutdown();
+    }
+}
+
+impl Shared {
+    fn request_shutdown(&self) {
+        self.stopping.store(true, Ordering::Release);
+        self.shutdown.notify(usize::MAX);
+    }
+
+    async fn wait_for_shutdown(&self) {
+        loop {
+            let listener = self.shutdown.listen();
+            if self.stopping.load(Ordering::Acquire) {
+                return;
+            }
+            listener.await;
+        }
+    }
+
+    async fn wait_for_space(&self, len: usize) {
+        loop {
+            let listener = self.space_ready.listen();
+
+            {
+                let ring = self.ring.lock().unwrap();
+                if ring.capacity - ring.data.len() >= len {
+                    return;
+                }
+            }
+
+            listener.await;
+        }
+    }
+}
+
+async fn run(
+    shared: Arc<Shared>,
+    ep_in: Arc<Mutex<Box<dyn BulkEndpoint>>>,
+    decoder: FtdiPacketDecoder,
+    transfer_size: usize,
+    queue_depth: usize,
+) {
+    let mut ep_in = ep_in.lock_arc().await;
+    let pool = BufferPool::default();
+
+    loop {
+        future::or(
+            shared.wait_for_shutdown(),
+            shared.wait_for_space(transfer_size * queue_depth),
+        )
+        .await;
+
+        if shared.stopping.load(Ordering::Acquire) {
+            return;
+        }
+
+        while ep_in.pending() < queue_depth {
+            let buffer = pool.take(&mut **ep_in, transfer_size);
+            ep_in.submit(buffer);
+        }
+
+        let completion = future::or(async { Some(ep_in.next_complete().await) }, async {
+            shared.wait_for_shutdown().await;
+            None
+        })
+        .await;
+
+        let Some(completion) = completion else {
+            ep_in.cancel_all();
+            while ep_in.pending() > 0 {
+                ep_in.next_complete().await;
+            }
+            return;
+        };
+
+        shared.connection.observe(&completion.status);
+        shared.counters.record_read(
+            &completion.status,
+            &completion.buffer,
+            transfer_size,
+            decoder,
+        );
+
+        let failed = {
+            let mut ring = shared.ring.lock().unwrap();
+
+            match &completion.status {
+                Ok(()) => {
+                    #[cfg(feature = "trace")]
+                    crate::trace::bulk_in(ep_in.endpoint_address(), &completion.buffer);
+
+                    shared
+                        .line_monitor
+                        .record_packets(&completion.buffer, decoder);
+                    decoder.decode_into(&completion.buffer, &mut ring.data);
+                    false
+                }
+                Err(err) => {
+                    ring.error = Some(*err);
+                    true
+                }
+            }
+        };
+        pool.put(completion.buffer, queue_depth);
+
+        shared.data_ready.notify(usize::MAX);
+
+        if failed {
+            return;
+        }
+    }
+}
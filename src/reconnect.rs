@@ -0,0 +1,108 @@
+use core::future::Future;
+use core::time::Duration;
+
+use async_lock::Mutex;
+use futures_lite::StreamExt;
+
+use crate::mpsse::MpsseInterface;
+use crate::{
+    list_devices_with_filter, watch_devices_with_filter, DeviceEvent, DeviceFilter, DeviceInfo,
+    Error, Interface, OpenOptions, PortLetter, Result,
+};
+
+const ARRIVAL_SETTLE: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub struct ReconnectingInterface {
+    serial_number: String,
+    port: PortLetter,
+    options: OpenOptions,
+    current: Mutex<Option<Interface>>,
+}
+
+impl ReconnectingInterface {
+    pub async fn open(serial_number: &str, port: PortLetter, options: OpenOptions) -> Result<Self> {
+        let reconnecting = ReconnectingInterface {
+            serial_number: serial_number.into(),
+            port,
+            options,
+            current: Mutex::new(None),
+        };
+        reconnecting.interface().await?;
+
+        Ok(reconnecting)
+    }
+
+    pub async fn interface(&self) -> Result<Interface> {
+        let mut current = self.current.lock().await;
+
+        if let Some(interface) = current.as_ref().filter(|i| !i.is_disconnected()) {
+            return Ok(interface.clone());
+        }
+
+        let interface = self.reconnect(current.as_ref()).await?;
+        *current = Some(interface.clone());
+
+        Ok(interface)
+    }
+
+    pub async fn set_low_data_bits(&self, value: u8, direction: u8) -> Result<()> {
+        self.interface()
+            .await?
+            .set_low_data_bits(value, direction)
+            .await
+    }
+
+    pub async fn set_high_data_bits(&self, value: u8, direction: u8) -> Result<()> {
+        self.interface()
+            .await?
+            .set_high_data_bits(value, direction)
+            .await
+    }
+
+    pub async fn run<F, Fut, T>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut(Interface) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        loop {
+            let interface = self.interface().await?;
+
+            match op(interface).await {
+                Err(Error::Disconnected) => continue,
+                res => return res,
+            }
+        }
+    }
+
+    async fn reconnect(&self, previous: Option<&Interface>) -> Result<Interface> {
+        let filter = DeviceFilter::new().serial_number(&self.serial_number);
+        let mut watcher = watch_devices_with_filter(filter.clone()).await?;
+
+        if let Some(dev) = list_devices_with_filter(filter).await?.next() {
+            return self.restore(dev, previous).await;
+        }
+
+        loop {
+            match watcher.next().await {
+                Some(DeviceEvent::Arrived(dev)) => {
+                    futures_timer::Delay::new(ARRIVAL_SETTLE).await;
+                    return self.restore(dev, previous).await;
+                }
+                Some(DeviceEvent::Left(_)) => {}
+                None => return Err(Error::DeviceNotFound),
+            }
+        }
+    }
+
+    async fn restore(&self, dev: DeviceInfo, previous: Option<&Interface>) -> Result<Interface> {
+        let mut interface = dev.open_port(self.port).await?;
+        self.options.apply(&interface).await?;
+
+        if let Some(previous) = previous {
+            interface.restore_from(previous).await?;
+        }
+
+        Ok(interface)
+    }
+}
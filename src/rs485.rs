@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use crate::cbus::CbusGpio;
+use crate::{DeviceType, Error, Interface, Result};
+
+const FT232R_CBUS_TXDEN: u8 = 0x00;
+const FTX_CBUS_TXDEN: u8 = 0x09;
+const FT232H_CBUS_TXDEN: u8 = 0x09;
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+const ECHO_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Debug)]
+pub enum DirectionControl {
+    Txden,
+    Rts,
+    Dtr,
+    Cbus {
+        gpio: CbusGpio,
+        pin: u8,
+        active_high: bool,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Rs485 {
+    interface: Interface,
+    direction: DirectionControl,
+    suppress_echo: bool,
+}
+
+impl Rs485 {
+    pub async fn new(interface: Interface, direction: DirectionControl) -> Result<Self> {
+        if let DirectionControl::Txden = direction {
+            let txden = match interface.device_type {
+                DeviceType::FT232R => FT232R_CBUS_TXDEN,
+                DeviceType::FtX => FTX_CBUS_TXDEN,
+                DeviceType::FT232H => FT232H_CBUS_TXDEN,
+                device_type => {
+                    return Err(Error::InvalidParameter(format!(
+                        "txden is not available on {:?}",
+                        device_type
+                    )))
+                }
+            };
+
+            let eeprom = interface.read_eeprom().await?;
+            if !eeprom.cbus.contains(&txden) {
+                return Err(Error::InvalidParameter(
+                    "no cbus pin is configured for txden in the eeprom".into(),
+                ));
+            }
+        }
+
+        let rs485 = Rs485 {
+            interface,
+            direction,
+            suppress_echo: false,
+        };
+        rs485.set_transmit(false).await?;
+
+        Ok(rs485)
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn into_inner(self) -> Interface {
+        self.interface
+    }
+
+    pub fn set_echo_suppression(&mut self, suppress: bool) {
+        self.suppress_echo = suppress;
+    }
+
+    pub async fn write(&self, data: &[u8]) -> Result<()> {
+        self.set_transmit(true).await?;
+        let res = self.transmit(data).await;
+        self.set_transmit(false).await?;
+        res?;
+
+        if self.suppress_echo {
+            let mut echo = vec![0u8; data.len()];
+            let completed = self
+                .interface
+                .read_exact_timeout(&mut echo, ECHO_TIMEOUT)
+                .await?;
+            echo.truncate(completed);
+            if echo != data[..completed] {
+                return Err(Error::InvalidResponse(echo));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        self.interface.read(buf).await
+    }
+
+    pub async fn read_timeout(&self, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        self.interface.read_timeout(buf, timeout).await
+    }
+
+    async fn transmit(&self, data: &[u8]) -> Result<()> {
+        self.interface.write_all(data.to_vec()).await?;
+
+        let deadline = Instant::now() + DRAIN_TIMEOUT;
+        while !self.interface.status().await?.temt {
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout {
+                    operation: "rs485 drain",
+                    requested: data.len(),
+                    completed: data.len(),
+                });
+            }
+
+            futures_timer::Delay::new(DRAIN_POLL_INTERVAL).await;
+        }
+
+        Ok(())
+    }
+
+    async fn set_transmit(&self, transmit: bool) -> Result<()> {
+        match &self.direction {
+            DirectionControl::Txden => Ok(()),
+            DirectionControl::Rts if transmit => self.interface.set_rts().await,
+            DirectionControl::Rts => self.interface.clear_rts().await,
+            DirectionControl::Dtr if transmit => self.interface.set_dtr().await,
+            DirectionControl::Dtr => self.interface.clear_dtr().await,
+            DirectionControl::Cbus {
+                gpio,
+                pin,
+                active_high,
+            } => {
+                let value = if transmit == *active_high {
+                    1 << pin
+                } else {
+                    0
+                };
+                gpio.set_cbus_pins(1 << pin, value).await
+            }
+        }
+    }
+}
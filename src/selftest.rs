@@ -0,0 +1,46 @@
+use core::time::Duration;
+
+pub(crate) const PATTERN_LEN: usize = 256;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub sent: usize,
+    pub received: usize,
+    pub mismatches: usize,
+    pub first_mismatch: Option<usize>,
+    pub elapsed: Duration,
+}
+
+impl SelfTestReport {
+    pub(crate) fn compare(expected: &[u8], actual: &[u8], elapsed: Duration) -> Self {
+        let mut mismatches = expected
+            .iter()
+            .zip(actual)
+            .enumerate()
+            .filter(|(_, (expected, actual))| expected != actual)
+            .map(|(i, _)| i);
+
+        let first_mismatch = mismatches.next();
+
+        SelfTestReport {
+            sent: expected.len(),
+            received: actual.len(),
+            mismatches: first_mismatch.map_or(0, |_| mismatches.count() + 1),
+            first_mismatch,
+            elapsed,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.received == self.sent && self.mismatches == 0
+    }
+}
+
+pub(crate) fn pattern(len: usize) -> Vec<u8> {
+    [0x00, 0xff, 0x55, 0xaa]
+        .into_iter()
+        .chain((0..=255).rev())
+        .cycle()
+        .take(len)
+        .collect()
+}
@@ -0,0 +1,315 @@
+#[cfg(feature = "futures-io")]
+use core::pin::Pin;
+#[cfg(feature = "futures-io")]
+use core::task::{Context, Poll};
+#[cfg(feature = "futures-io")]
+use std::io;
+use std::time::Duration;
+
+#[cfg(feature = "futures-io")]
+use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+
+use crate::{
+    DataBits, Error, FlowControl, Interface, LineSettings, Parity, PortLetter, Result, StopBits,
+    Uart,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearBuffer {
+    Input,
+    Output,
+    All,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerialPortBuilder {
+    path: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    parity: Parity,
+    stop_bits: StopBits,
+    flow_control: FlowControl,
+    timeout: Duration,
+}
+
+pub fn new(path: &str, baud_rate: u32) -> SerialPortBuilder {
+    SerialPortBuilder {
+        path: path.into(),
+        baud_rate,
+        data_bits: DataBits::Eight,
+        parity: Parity::None,
+        stop_bits: StopBits::One,
+        flow_control: FlowControl::None,
+        timeout: DEFAULT_TIMEOUT,
+    }
+}
+
+impl SerialPortBuilder {
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    pub fn baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    pub fn data_bits(mut self, data_bits: DataBits) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    pub fn parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    pub fn stop_bits(mut self, stop_bits: StopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn open_async(self) -> Result<SerialPort> {
+        let (serial_number, port) = parse_path(&self.path)?;
+        let interface = Interface::with_serial_number(serial_number, port).await?;
+        let uart = interface.into_uart().await?;
+
+        let mut port = SerialPort {
+            uart,
+            name: self.path,
+            baud_rate: self.baud_rate,
+            line: LineSettings {
+                data_bits: self.data_bits,
+                parity: self.parity,
+                stop_bits: self.stop_bits,
+            },
+            flow_control: self.flow_control,
+            timeout: self.timeout,
+        };
+        port.set_baud_rate(self.baud_rate).await?;
+        port.interface().set_line_settings(port.line).await?;
+        port.set_flow_control(self.flow_control).await?;
+        port.set_timeout(self.timeout);
+
+        Ok(port)
+    }
+}
+
+fn parse_path(path: &str) -> Result<(&str, PortLetter)> {
+    match path.rsplit_once(':') {
+        Some((serial_number, port)) => {
+            let mut chars = port.chars();
+            match (chars.next(), chars.next()) {
+                (Some(letter), None) => Ok((serial_number, PortLetter::try_from(letter)?)),
+                _ => Err(Error::InvalidParameter(format!(
+                    "invalid port in serial path {:?}",
+                    path
+                ))),
+            }
+        }
+        None => Ok((path, PortLetter::A)),
+    }
+}
+
+#[derive(Debug)]
+pub struct SerialPort {
+    uart: Uart,
+    name: String,
+    baud_rate: u32,
+    line: LineSettings,
+    flow_control: FlowControl,
+    timeout: Duration,
+}
+
+impl SerialPort {
+    pub fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    pub fn interface(&self) -> &Interface {
+        self.uart.interface()
+    }
+
+    pub fn into_inner(self) -> Interface {
+        self.uart.into_inner()
+    }
+
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    pub fn data_bits(&self) -> DataBits {
+        self.line.data_bits
+    }
+
+    pub fn parity(&self) -> Parity {
+        self.line.parity
+    }
+
+    pub fn stop_bits(&self) -> StopBits {
+        self.line.stop_bits
+    }
+
+    pub fn flow_control(&self) -> FlowControl {
+        self.flow_control
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub async fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.baud_rate = self.interface().set_baudrate(baud_rate).await?;
+
+        Ok(())
+    }
+
+    pub async fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        self.set_line(LineSettings {
+            data_bits,
+            ..self.line
+        })
+        .await
+    }
+
+    pub async fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        self.set_line(LineSettings {
+            parity,
+            ..self.line
+        })
+        .await
+    }
+
+    pub async fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        self.set_line(LineSettings {
+            stop_bits,
+            ..self.line
+        })
+        .await
+    }
+
+    pub async fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        self.interface().set_flow_control(flow_control).await?;
+        self.flow_control = flow_control;
+
+        Ok(())
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        let io_timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+        self.interface()
+            .set_read_write_timeouts(io_timeout, io_timeout);
+        self.timeout = timeout;
+    }
+
+    pub async fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        match level {
+            true => self.interface().set_rts().await,
+            false => self.interface().clear_rts().await,
+        }
+    }
+
+    pub async fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        match level {
+            true => self.interface().set_dtr().await,
+            false => self.interface().clear_dtr().await,
+        }
+    }
+
+    pub async fn read_clear_to_send(&mut self) -> Result<bool> {
+        Ok(self.interface().status().await?.cts)
+    }
+
+    pub async fn read_data_set_ready(&mut self) -> Result<bool> {
+        Ok(self.interface().status().await?.dsr)
+    }
+
+    pub async fn read_ring_indicator(&mut self) -> Result<bool> {
+        Ok(self.interface().status().await?.ri)
+    }
+
+    pub async fn read_carrier_detect(&mut self) -> Result<bool> {
+        Ok(self.interface().status().await?.rlsd)
+    }
+
+    pub async fn bytes_to_read(&self) -> Result<u32> {
+        Ok(self.interface().bytes_buffered().await as u32)
+    }
+
+    pub async fn clear(&self, buffer: ClearBuffer) -> Result<()> {
+        match buffer {
+            ClearBuffer::Input => self.interface().purge_rx().await,
+            ClearBuffer::Output => self.interface().purge_tx().await,
+            ClearBuffer::All => self.interface().purge_all().await,
+        }
+    }
+
+    pub async fn set_break(&self) -> Result<()> {
+        self.interface().set_break(true).await
+    }
+
+    pub async fn clear_break(&self) -> Result<()> {
+        self.interface().set_break(false).await
+    }
+
+    async fn set_line(&mut self, line: LineSettings) -> Result<()> {
+        self.interface().set_line_settings(line).await?;
+        self.line = line;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl AsyncRead for SerialPort {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().uart).poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl AsyncBufRead for SerialPort {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().uart).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.get_mut().uart).consume(amt)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl AsyncWrite for SerialPort {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().uart).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().uart).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().uart).poll_close(cx)
+    }
+}
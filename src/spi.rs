@@ -0,0 +1,431 @@
+pub mod flash;
+
+use std::sync::Arc;
+
+use async_lock::Mutex;
+use kftdi_core::spi::SCK;
+
+pub use kftdi_core::spi::SpiMode;
+
+use crate::gpio::Bank;
+use crate::mpsse::{BitOrder, ClockEdge, Mpsse, MpsseCmdBuilder, MpsseInterface};
+use crate::pins::PinBank;
+use crate::{Error, Interface, PinClaim, Result};
+
+const MOSI: u8 = 1 << 1;
+const MISO: u8 = 1 << 2;
+
+#[derive(Clone, Debug)]
+pub struct SpiBus {
+    interface: Interface,
+    pins: Arc<PinClaim>,
+    value: u8,
+    direction: u8,
+    mode: SpiMode,
+    order: BitOrder,
+}
+
+impl SpiBus {
+    pub async fn new(mpsse: Mpsse, frequency: u32) -> Result<Self> {
+        let interface = mpsse.into_inner();
+        interface.disable_3phase_clocking().await?;
+        interface.set_frequency(frequency).await?;
+
+        let pins = Arc::new(interface.claim_pins("spi", (SCK | MOSI | MISO) as u16)?);
+        let mut bus = SpiBus {
+            interface,
+            pins,
+            value: 0,
+            direction: SCK | MOSI,
+            mode: SpiMode::Mode0,
+            order: BitOrder::MsbFirst,
+        };
+
+        bus.write_low().await?;
+
+        Ok(bus)
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub async fn set_frequency(&self, frequency: u32) -> Result<u32> {
+        self.interface.set_frequency(frequency).await
+    }
+
+    pub fn mode(&self) -> SpiMode {
+        self.mode
+    }
+
+    pub async fn set_mode(&mut self, mode: SpiMode) -> Result<()> {
+        self.value = (self.value & !SCK) | mode.idle();
+        self.mode = mode;
+
+        self.write_low().await
+    }
+
+    pub fn bit_order(&self) -> BitOrder {
+        self.order
+    }
+
+    pub fn set_bit_order(&mut self, order: BitOrder) {
+        self.order = order;
+    }
+
+    pub async fn set_cs(&mut self, mask: u8, active: bool) -> Result<()> {
+        self.pins.extend(mask as u16)?;
+        self.direction |= mask;
+
+        if active {
+            self.value &= !mask;
+        } else {
+            self.value |= mask;
+        }
+
+        self.write_low().await
+    }
+
+    async fn write_low(&mut self) -> Result<()> {
+        let (value, direction) = (self.value, self.direction);
+        (self.value, self.direction) = self
+            .pins
+            .write(&self.interface, Bank::Low, |v, d| {
+                *v = value;
+                *d = direction;
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn transfer(&self, buf: &mut [u8]) -> Result<()> {
+        let cmd = MpsseCmdBuilder::new()
+            .clock_bytes_inout(self.mode.out_edge(), self.order, buf)
+            .send_immediate();
+
+        let res = self.interface.execute(cmd).await?;
+        buf.copy_from_slice(&res);
+
+        Ok(())
+    }
+
+    pub async fn write(&self, buf: &[u8]) -> Result<()> {
+        let cmd = MpsseCmdBuilder::new().clock_bytes_out(self.mode.out_edge(), self.order, buf);
+
+        self.interface.execute(cmd).await?;
+
+        Ok(())
+    }
+
+    pub async fn read(&self, buf: &mut [u8]) -> Result<()> {
+        let cmd = MpsseCmdBuilder::new()
+            .clock_bytes_in(self.mode.in_edge(), self.order, buf.len())
+            .send_immediate();
+
+        let res = self.interface.execute(cmd).await?;
+        buf.copy_from_slice(&res);
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChipSelect {
+    pub bank: Bank,
+    pub pin: u8,
+    pub active_high: bool,
+}
+
+impl ChipSelect {
+    pub fn adbus(pin: u8) -> Self {
+        ChipSelect {
+            bank: Bank::Low,
+            pin,
+            active_high: false,
+        }
+    }
+
+    pub fn acbus(pin: u8) -> Self {
+        ChipSelect {
+            bank: Bank::High,
+            pin,
+            active_high: false,
+        }
+    }
+
+    pub fn active_high(mut self) -> Self {
+        self.active_high = true;
+        self
+    }
+
+    fn mask(self) -> u8 {
+        1 << self.pin
+    }
+
+    fn apply(self, value: u8, active: bool) -> u8 {
+        if active == self.active_high {
+            value | self.mask()
+        } else {
+            value & !self.mask()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    bus: SpiBus,
+    high_value: u8,
+    high_direction: u8,
+}
+
+impl Shared {
+    fn select(
+        &self,
+        pins: &mut PinBank<'_>,
+        cmd: MpsseCmdBuilder,
+        cs: ChipSelect,
+        mode: SpiMode,
+        active: bool,
+        mosi: bool,
+    ) -> MpsseCmdBuilder {
+        let low = (self.bus.value & !(SCK | MOSI)) | mode.idle() | if mosi { MOSI } else { 0 };
+
+        match cs.bank {
+            Bank::Low => {
+                let (value, direction) = pins
+                    .select(Bank::Low)
+                    .compose(cs.apply(low, active), self.bus.direction);
+                cmd.set_low_data_bits(value, direction)
+            }
+            Bank::High => {
+                let (value, direction) = pins.select(Bank::Low).compose(low, self.bus.direction);
+                let cmd = cmd.set_low_data_bits(value, direction);
+                let (value, direction) = pins
+                    .select(Bank::High)
+                    .compose(cs.apply(self.high_value, active), self.high_direction);
+                cmd.set_high_data_bits(value, direction)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpiBusManager {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl SpiBusManager {
+    pub fn new(bus: SpiBus) -> Self {
+        SpiBusManager {
+            shared: Arc::new(Mutex::new(Shared {
+                bus,
+                high_value: 0,
+                high_direction: 0,
+            })),
+        }
+    }
+
+    pub async fn device(&self, cs: ChipSelect) -> Result<SpiDevice> {
+        let valid = match cs.bank {
+            Bank::Low => (3..8).contains(&cs.pin),
+            Bank::High => cs.pin < 8,
+        };
+        if !valid {
+            return Err(Error::InvalidParameter(format!(
+                "chip select must be ADBUS3..ADBUS7 or ACBUS0..ACBUS7, got {:?}{}",
+                cs.bank, cs.pin
+            )));
+        }
+
+        let mut shared = self.shared.lock().await;
+        let interface = shared.bus.interface.clone();
+
+        match cs.bank {
+            Bank::Low => {
+                shared.bus.pins.extend(cs.mask() as u16)?;
+                shared.bus.direction |= cs.mask();
+                shared.bus.value = cs.apply(shared.bus.value, false);
+                shared.bus.write_low().await?;
+            }
+            Bank::High => {
+                shared.bus.pins.extend((cs.mask() as u16) << 8)?;
+                let value = cs.apply(shared.high_value, false);
+                let direction = shared.high_direction | cs.mask();
+                (shared.high_value, shared.high_direction) = shared
+                    .bus
+                    .pins
+                    .write(&interface, Bank::High, |v, d| {
+                        *v = value;
+                        *d = direction;
+                    })
+                    .await?;
+            }
+        }
+
+        Ok(SpiDevice {
+            shared: self.shared.clone(),
+            cs,
+            mode: shared.bus.mode,
+            order: shared.bus.order,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpiDevice {
+    shared: Arc<Mutex<Shared>>,
+    cs: ChipSelect,
+    mode: SpiMode,
+    order: BitOrder,
+}
+
+impl SpiDevice {
+    pub async fn new(manager: &SpiBusManager, cs_pin: u8) -> Result<Self> {
+        manager.device(ChipSelect::adbus(cs_pin)).await
+    }
+
+    pub fn manager(&self) -> SpiBusManager {
+        SpiBusManager {
+            shared: self.shared.clone(),
+        }
+    }
+
+    pub fn chip_select(&self) -> ChipSelect {
+        self.cs
+    }
+
+    pub fn mode(&self) -> SpiMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: SpiMode) {
+        self.mode = mode;
+    }
+
+    pub fn bit_order(&self) -> BitOrder {
+        self.order
+    }
+
+    pub fn set_bit_order(&mut self, order: BitOrder) {
+        self.order = order;
+    }
+
+    pub async fn transfer(&mut self, buf: &mut [u8]) -> Result<()> {
+        let (mode, order) = (self.mode, self.order);
+        let res = self
+            .transaction(buf.first().copied(), |cmd| {
+                cmd.clock_bytes_inout(mode.out_edge(), order, buf)
+            })
+            .await?;
+        buf.copy_from_slice(&res);
+
+        Ok(())
+    }
+
+    pub async fn write(&mut self, buf: &[u8]) -> Result<()> {
+        let (mode, order) = (self.mode, self.order);
+        self.transaction(buf.first().copied(), |cmd| {
+            cmd.clock_bytes_out(mode.out_edge(), order, buf)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<()> {
+        let (mode, order) = (self.mode, self.order);
+        let res = self
+            .transaction(None, |cmd| {
+                cmd.clock_bytes_in(mode.in_edge(), order, buf.len())
+            })
+            .await?;
+        buf.copy_from_slice(&res);
+
+        Ok(())
+    }
+
+    pub async fn write_read(&mut self, write: &[u8], read: &mut [u8]) -> Result<()> {
+        let (mode, order) = (self.mode, self.order);
+        let res = self
+            .transaction(write.first().copied(), |cmd| {
+                cmd.clock_bytes_out(mode.out_edge(), order, write)
+                    .clock_bytes_in(mode.in_edge(), order, read.len())
+            })
+            .await?;
+        read.copy_from_slice(&res);
+
+        Ok(())
+    }
+
+    pub async fn transfer_bits(&mut self, buf: &mut [u8], bits: usize) -> Result<()> {
+        if buf.len() != bits.div_ceil(8) || bits == 0 {
+            return Err(Error::InvalidParameter(format!(
+                "{bits} bits requires a {} byte buffer, got {}",
+                bits.div_ceil(8),
+                buf.len()
+            )));
+        }
+
+        let (mode, order) = (self.mode, self.order);
+        let whole = bits / 8;
+        let tail = (bits % 8) as u8;
+        let last = buf[buf.len() - 1];
+
+        let res = self
+            .transaction(buf.first().copied(), |cmd| {
+                let cmd = cmd.clock_bytes_inout(mode.out_edge(), order, &buf[..whole]);
+                if tail > 0 {
+                    cmd.clock_bits_inout(mode.out_edge(), order, last, tail)
+                } else {
+                    cmd
+                }
+            })
+            .await?;
+
+        buf[..whole].copy_from_slice(&res[..whole]);
+        if tail > 0 {
+            buf[whole] = match order {
+                BitOrder::MsbFirst => res[whole] << (8 - tail),
+                BitOrder::LsbFirst => res[whole] >> (8 - tail),
+            };
+        }
+
+        Ok(())
+    }
+
+    async fn transaction(
+        &mut self,
+        first: Option<u8>,
+        build: impl FnOnce(MpsseCmdBuilder) -> MpsseCmdBuilder,
+    ) -> Result<Vec<u8>> {
+        let shared = self.shared.lock().await;
+
+        // With CPHA set the slave samples on the trailing edge, but the MPSSE
+        // only shifts the first bit out on the leading edge. Put it on MOSI
+        // while asserting chip select so it is stable for the whole clock.
+        let mosi = match (self.mode.cpha(), first) {
+            (true, Some(byte)) => match self.order {
+                BitOrder::MsbFirst => byte & 0x80 != 0,
+                BitOrder::LsbFirst => byte & 0x01 != 0,
+            },
+            _ => false,
+        };
+
+        let mut pins = shared.bus.pins.bank(Bank::Low).await;
+        let cmd = shared.select(
+            &mut pins,
+            MpsseCmdBuilder::new(),
+            self.cs,
+            self.mode,
+            true,
+            mosi,
+        );
+        let cmd = build(cmd);
+        let cmd = shared
+            .select(&mut pins, cmd, self.cs, self.mode, false, false)
+            .send_immediate();
+
+        shared.bus.interface.execute(cmd).await
+    }
+}
@@ -0,0 +1,341 @@
+use std::time::{Duration, Instant};
+
+use super::SpiDevice;
+use crate::{Error, Result};
+
+const READ_JEDEC_ID: u8 = 0x9f;
+const READ_SFDP: u8 = 0x5a;
+const READ_STATUS: u8 = 0x05;
+const WRITE_ENABLE: u8 = 0x06;
+const PAGE_PROGRAM: u8 = 0x02;
+const FAST_READ: u8 = 0x0b;
+const SECTOR_ERASE: u8 = 0x20;
+const BLOCK_ERASE: u8 = 0xd8;
+const CHIP_ERASE: u8 = 0xc7;
+
+const STATUS_BUSY: u8 = 1 << 0;
+
+const SFDP_SIGNATURE: &[u8; 4] = b"SFDP";
+const SFDP_BASIC_TABLE: u16 = 0xff00;
+
+const SECTOR_SIZE: u32 = 0x1000;
+const BLOCK_SIZE: u32 = 0x10000;
+const DEFAULT_PAGE_SIZE: usize = 256;
+const READ_CHUNK: usize = 0x10000;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+const PROGRAM_TIMEOUT: Duration = Duration::from_millis(50);
+const SECTOR_ERASE_TIMEOUT: Duration = Duration::from_secs(2);
+const BLOCK_ERASE_TIMEOUT: Duration = Duration::from_secs(10);
+const CHIP_ERASE_TIMEOUT: Duration = Duration::from_secs(400);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JedecId {
+    pub manufacturer: u8,
+    pub memory_type: u8,
+    pub capacity: u8,
+}
+
+impl JedecId {
+    pub fn capacity_bytes(&self) -> Option<u32> {
+        match self.capacity {
+            0x10..=0x1f => Some(1 << self.capacity),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EraseType {
+    pub size: u32,
+    pub opcode: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sfdp {
+    pub major: u8,
+    pub minor: u8,
+    pub capacity: u64,
+    pub page_size: usize,
+    pub erase_types: Vec<EraseType>,
+}
+
+impl Sfdp {
+    pub fn parse(header: &[u8], table: &[u8]) -> Result<Self> {
+        if header.len() < 8 || &header[..4] != SFDP_SIGNATURE || table.len() < 8 {
+            return Err(Error::InvalidResponse(header.to_vec()));
+        }
+
+        let dword = |n: usize| {
+            table
+                .get(n * 4..n * 4 + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        let density = dword(1).unwrap_or_default();
+        let capacity = if density & (1 << 31) != 0 {
+            1u64.checked_shl((density & 0x7fff_ffff).saturating_sub(3))
+                .unwrap_or(u64::MAX)
+        } else {
+            (density as u64 + 1) / 8
+        };
+
+        let mut erase_types = Vec::new();
+        if let (Some(lo), Some(hi)) = (dword(7), dword(8)) {
+            let bytes = [lo.to_le_bytes(), hi.to_le_bytes()].concat();
+            for pair in bytes.chunks(2) {
+                if pair[0] != 0 {
+                    erase_types.push(EraseType {
+                        size: 1 << pair[0].min(31),
+                        opcode: pair[1],
+                    });
+                }
+            }
+        }
+
+        let page_size = match dword(10) {
+            Some(word) => 1 << ((word >> 4) & 0x0f),
+            None => DEFAULT_PAGE_SIZE,
+        };
+
+        Ok(Sfdp {
+            major: header[5],
+            minor: header[4],
+            capacity,
+            page_size,
+            erase_types,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Flash {
+    spi: SpiDevice,
+    page_size: usize,
+}
+
+impl Flash {
+    pub fn new(spi: SpiDevice) -> Self {
+        Flash {
+            spi,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn spi(&mut self) -> &mut SpiDevice {
+        &mut self.spi
+    }
+
+    pub fn into_inner(self) -> SpiDevice {
+        self.spi
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub fn set_page_size(&mut self, page_size: usize) -> Result<()> {
+        if !page_size.is_power_of_two() {
+            return Err(Error::InvalidParameter(format!(
+                "flash page size must be a power of two, got {}",
+                page_size
+            )));
+        }
+
+        self.page_size = page_size;
+
+        Ok(())
+    }
+
+    pub async fn read_jedec_id(&mut self) -> Result<JedecId> {
+        let mut id = [0u8; 3];
+        self.spi.write_read(&[READ_JEDEC_ID], &mut id).await?;
+
+        Ok(JedecId {
+            manufacturer: id[0],
+            memory_type: id[1],
+            capacity: id[2],
+        })
+    }
+
+    pub async fn read_sfdp(&mut self) -> Result<Sfdp> {
+        let mut header = [0u8; 8];
+        self.sfdp_read(0, &mut header).await?;
+        if &header[..4] != SFDP_SIGNATURE {
+            return Err(Error::InvalidResponse(header.to_vec()));
+        }
+
+        let headers = header[6] as usize + 1;
+        let mut params = vec![0u8; headers * 8];
+        self.sfdp_read(8, &mut params).await?;
+
+        let basic = params
+            .chunks(8)
+            .find(|p| u16::from_le_bytes([p[0], p[7]]) == SFDP_BASIC_TABLE)
+            .ok_or_else(|| Error::InvalidResponse(params.clone()))?;
+
+        let pointer = u32::from_le_bytes([basic[4], basic[5], basic[6], 0]);
+        let mut table = vec![0u8; basic[3] as usize * 4];
+        self.sfdp_read(pointer, &mut table).await?;
+
+        let sfdp = Sfdp::parse(&header, &table)?;
+        self.page_size = sfdp.page_size;
+
+        Ok(sfdp)
+    }
+
+    pub async fn read_status(&mut self) -> Result<u8> {
+        let mut status = [0u8];
+        self.spi.write_read(&[READ_STATUS], &mut status).await?;
+
+        Ok(status[0])
+    }
+
+    pub async fn wait_ready(&mut self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if self.read_status().await? & STATUS_BUSY == 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout {
+                    operation: "flash wait_ready",
+                    requested: 0,
+                    completed: 0,
+                });
+            }
+
+            futures_timer::Delay::new(POLL_INTERVAL).await;
+        }
+    }
+
+    pub async fn write_enable(&mut self) -> Result<()> {
+        self.spi.write(&[WRITE_ENABLE]).await
+    }
+
+    pub async fn erase_sector(&mut self, address: u32) -> Result<()> {
+        self.erase(SECTOR_ERASE, address, SECTOR_ERASE_TIMEOUT)
+            .await
+    }
+
+    pub async fn erase_block(&mut self, address: u32) -> Result<()> {
+        self.erase(BLOCK_ERASE, address, BLOCK_ERASE_TIMEOUT).await
+    }
+
+    pub async fn erase_chip(&mut self) -> Result<()> {
+        self.write_enable().await?;
+        self.spi.write(&[CHIP_ERASE]).await?;
+        self.wait_ready(CHIP_ERASE_TIMEOUT).await
+    }
+
+    pub async fn erase_range(
+        &mut self,
+        address: u32,
+        len: u32,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<()> {
+        if address % SECTOR_SIZE != 0 || len % SECTOR_SIZE != 0 {
+            return Err(Error::InvalidParameter(format!(
+                "erase range {:#x}+{:#x} is not sector aligned",
+                address, len
+            )));
+        }
+
+        let end = address + len;
+        let mut offset = address;
+        while offset < end {
+            if offset % BLOCK_SIZE == 0 && end - offset >= BLOCK_SIZE {
+                self.erase_block(offset).await?;
+                offset += BLOCK_SIZE;
+            } else {
+                self.erase_sector(offset).await?;
+                offset += SECTOR_SIZE;
+            }
+
+            progress((offset - address) as usize, len as usize);
+        }
+
+        Ok(())
+    }
+
+    pub async fn read(&mut self, address: u32, buf: &mut [u8]) -> Result<()> {
+        for (i, chunk) in buf.chunks_mut(READ_CHUNK).enumerate() {
+            let start = address + (i * READ_CHUNK) as u32;
+            let [_, a2, a1, a0] = start.to_be_bytes();
+            self.spi
+                .write_read(&[FAST_READ, a2, a1, a0, 0x00], chunk)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn program(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<()> {
+        let mut written = 0;
+
+        while written < data.len() {
+            let start = address + written as u32;
+            let room = self.page_size - (start as usize & (self.page_size - 1));
+            let len = room.min(data.len() - written);
+
+            let [_, a2, a1, a0] = start.to_be_bytes();
+            let mut cmd = vec![PAGE_PROGRAM, a2, a1, a0];
+            cmd.extend_from_slice(&data[written..written + len]);
+
+            self.write_enable().await?;
+            self.spi.write(&cmd).await?;
+            self.wait_ready(PROGRAM_TIMEOUT).await?;
+
+            written += len;
+            progress(written, data.len());
+        }
+
+        Ok(())
+    }
+
+    pub async fn verify(&mut self, address: u32, data: &[u8]) -> Result<()> {
+        let mut readback = vec![0u8; data.len()];
+        self.read(address, &mut readback).await?;
+
+        match data.iter().zip(&readback).position(|(a, b)| a != b) {
+            Some(offset) => Err(Error::FlashVerify {
+                address: address + offset as u32,
+                expected: data[offset],
+                actual: readback[offset],
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn write(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        progress: impl FnMut(usize, usize),
+    ) -> Result<()> {
+        self.program(address, data, progress).await?;
+        self.verify(address, data).await
+    }
+
+    async fn erase(&mut self, opcode: u8, address: u32, timeout: Duration) -> Result<()> {
+        let [_, a2, a1, a0] = address.to_be_bytes();
+
+        self.write_enable().await?;
+        self.spi.write(&[opcode, a2, a1, a0]).await?;
+        self.wait_ready(timeout).await
+    }
+
+    async fn sfdp_read(&mut self, address: u32, buf: &mut [u8]) -> Result<()> {
+        let [_, a2, a1, a0] = address.to_be_bytes();
+
+        self.spi
+            .write_read(&[READ_SFDP, a2, a1, a0, 0x00], buf)
+            .await
+    }
+}
@@ -0,0 +1,88 @@
+use core::time::Duration;
+use std::sync::Arc;
+
+use crate::{Interface, Result};
+
+#[derive(Debug)]
+pub struct ReadHalf {
+    interface: Interface,
+    id: Arc<()>,
+}
+
+#[derive(Debug)]
+pub struct WriteHalf {
+    interface: Interface,
+    id: Arc<()>,
+}
+
+pub(crate) fn split(interface: Interface) -> (ReadHalf, WriteHalf) {
+    let id = Arc::new(());
+
+    (
+        ReadHalf {
+            interface: interface.clone(),
+            id: id.clone(),
+        },
+        WriteHalf { interface, id },
+    )
+}
+
+impl ReadHalf {
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.interface.read(buf).await
+    }
+
+    pub async fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        self.interface.read_timeout(buf, timeout).await
+    }
+
+    pub async fn read_exact_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> Result<usize> {
+        self.interface.read_exact_timeout(buf, timeout).await
+    }
+
+    pub async fn read_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.interface.read_all(buf).await
+    }
+
+    pub fn reunite(self, write: WriteHalf) -> Result<Interface, (ReadHalf, WriteHalf)> {
+        if Arc::ptr_eq(&self.id, &write.id) {
+            Ok(write.interface)
+        } else {
+            Err((self, write))
+        }
+    }
+}
+
+impl WriteHalf {
+    pub async fn write_all(&mut self, buf: Vec<u8>) -> Result<()> {
+        self.interface.write_all(buf).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::transport::MockFtdi;
+    use crate::{DeviceType, Interface};
+
+    fn open(mock: &MockFtdi) -> Interface {
+        Interface::with_transport(Arc::new(mock.clone()), DeviceType::FT232R, 0).unwrap()
+    }
+
+    #[test]
+    fn reunite_matching_halves() {
+        let (read, write) = open(&MockFtdi::new(64)).split();
+
+        assert!(read.reunite(write).is_ok());
+    }
+
+    #[test]
+    fn reunite_rejects_halves_of_another_split() {
+        let interface = open(&MockFtdi::new(64));
+        let (read, _) = interface.clone().split();
+        let (_, write) = interface.split();
+
+        assert!(read.reunite(write).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use nusb::transfer::TransferError;
+
+use crate::FtdiPacketDecoder;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub read_transfers: u64,
+    pub write_transfers: u64,
+    pub control_transfers: u64,
+    pub retransmits: u64,
+    pub short_reads: u64,
+    pub errors: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    read_transfers: AtomicU64,
+    write_transfers: AtomicU64,
+    control_transfers: AtomicU64,
+    retransmits: AtomicU64,
+    short_reads: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn snapshot(&self) -> Stats {
+        Stats {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            read_transfers: self.read_transfers.load(Ordering::Relaxed),
+            write_transfers: self.write_transfers.load(Ordering::Relaxed),
+            control_transfers: self.control_transfers.load(Ordering::Relaxed),
+            retransmits: self.retransmits.load(Ordering::Relaxed),
+            short_reads: self.short_reads.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn record_read(
+        &self,
+        status: &Result<(), TransferError>,
+        buffer: &[u8],
+        transfer_size: usize,
+        decoder: FtdiPacketDecoder,
+    ) {
+        self.read_transfers.fetch_add(1, Ordering::Relaxed);
+
+        if status.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let payload = decoder.payload_len(buffer);
+        self.bytes_read.fetch_add(payload as u64, Ordering::Relaxed);
+
+        if buffer.len() < transfer_size {
+            self.short_reads.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_write(&self, status: &Result<(), TransferError>, len: usize) {
+        self.write_transfers.fetch_add(1, Ordering::Relaxed);
+
+        match status {
+            Ok(()) => self.bytes_written.fetch_add(len as u64, Ordering::Relaxed),
+            Err(_) => self.errors.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    pub(crate) fn record_control<T>(&self, status: &Result<T, TransferError>) {
+        self.control_transfers.fetch_add(1, Ordering::Relaxed);
+
+        if status.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_retransmit(&self) {
+        self.retransmits.fetch_add(1, Ordering::Relaxed);
+    }
+}
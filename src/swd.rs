@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use crate::gpio::Bank;
+use crate::mpsse::{BitOrder, ClockEdge, MpsseCmdBuilder, MpsseInterface};
+use crate::pins::PinBank;
+use crate::{Error, Interface, PinClaim, Result};
+
+const SWCLK: u8 = 1 << 0;
+const SWDIO_OUT: u8 = 1 << 1;
+const SWDIO_IN: u8 = 1 << 2;
+
+const ACK_OK: u8 = 0b001;
+const ACK_WAIT: u8 = 0b010;
+const ACK_FAULT: u8 = 0b100;
+
+const DP_IDCODE: u8 = 0x00;
+const DP_ABORT: u8 = 0x00;
+const DP_CTRL_STAT: u8 = 0x04;
+const DP_RDBUFF: u8 = 0x0c;
+const ABORT_CLEAR_ERRORS: u32 = 0x1e;
+
+const JTAG_TO_SWD: [u8; 2] = [0x9e, 0xe7];
+const LINE_RESET: [u8; 7] = [0xff; 7];
+const DEFAULT_WAIT_RETRIES: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Port {
+    Dp,
+    Ap,
+}
+
+#[derive(Clone, Debug)]
+pub struct Swd {
+    interface: Interface,
+    pins: Arc<PinClaim>,
+    wait_retries: u32,
+}
+
+impl Swd {
+    pub async fn new(interface: Interface, frequency: u32) -> Result<Self> {
+        let pins = Arc::new(interface.claim_pins("swd", (SWCLK | SWDIO_OUT | SWDIO_IN) as u16)?);
+        interface.initialize_mpsse().await?;
+        interface.set_frequency(frequency).await?;
+        pins.write(&interface, Bank::Low, |value, direction| {
+            *value = SWDIO_OUT;
+            *direction = SWCLK | SWDIO_OUT;
+        })
+        .await?;
+
+        Ok(Swd {
+            interface,
+            pins,
+            wait_retries: DEFAULT_WAIT_RETRIES,
+        })
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn into_inner(self) -> Interface {
+        self.interface
+    }
+
+    pub fn set_wait_retries(&mut self, retries: u32) {
+        self.wait_retries = retries;
+    }
+
+    pub async fn connect(&self) -> Result<u32> {
+        let cmd = MpsseCmdBuilder::new()
+            .clock_bytes_out(ClockEdge::Falling, BitOrder::LsbFirst, &LINE_RESET)
+            .clock_bytes_out(ClockEdge::Falling, BitOrder::LsbFirst, &JTAG_TO_SWD)
+            .clock_bytes_out(ClockEdge::Falling, BitOrder::LsbFirst, &LINE_RESET)
+            .clock_bytes_out(ClockEdge::Falling, BitOrder::LsbFirst, &[0x00]);
+        self.interface.execute(cmd).await?;
+
+        self.read_dp(DP_IDCODE).await
+    }
+
+    pub async fn line_reset(&self) -> Result<()> {
+        let cmd = MpsseCmdBuilder::new()
+            .clock_bytes_out(ClockEdge::Falling, BitOrder::LsbFirst, &LINE_RESET)
+            .clock_bytes_out(ClockEdge::Falling, BitOrder::LsbFirst, &[0x00]);
+        self.interface.execute(cmd).await?;
+
+        Ok(())
+    }
+
+    pub async fn read_dp(&self, address: u8) -> Result<u32> {
+        self.read(Port::Dp, address).await
+    }
+
+    pub async fn write_dp(&self, address: u8, value: u32) -> Result<()> {
+        self.write(Port::Dp, address, value).await
+    }
+
+    pub async fn read_ap(&self, address: u8) -> Result<u32> {
+        self.read(Port::Ap, address).await?;
+        self.read(Port::Dp, DP_RDBUFF).await
+    }
+
+    pub async fn write_ap(&self, address: u8, value: u32) -> Result<()> {
+        self.write(Port::Ap, address, value).await
+    }
+
+    pub async fn read(&self, port: Port, address: u8) -> Result<u32> {
+        self.request(port, true, address).await?;
+
+        let res = self
+            .execute(|pins| {
+                let (value, direction) = pins.compose(SWDIO_OUT, SWCLK | SWDIO_OUT);
+
+                MpsseCmdBuilder::new()
+                    .clock_bytes_in(ClockEdge::Rising, BitOrder::LsbFirst, 4)
+                    .clock_bits_in(ClockEdge::Rising, BitOrder::LsbFirst, 2)
+                    .set_low_data_bits(value, direction)
+                    .clock_bits_out(ClockEdge::Falling, BitOrder::LsbFirst, 0x00, 8)
+            })
+            .await?;
+
+        let value = u32::from_le_bytes([res[0], res[1], res[2], res[3]]);
+        let parity = (res[4] >> 6) & 1;
+        if value.count_ones() as u8 & 1 != parity {
+            return Err(Error::SwdParity);
+        }
+
+        Ok(value)
+    }
+
+    pub async fn write(&self, port: Port, address: u8, value: u32) -> Result<()> {
+        self.request(port, false, address).await?;
+
+        let parity = (value.count_ones() & 1) as u8;
+        self.execute(|pins| {
+            let (pin_value, direction) = pins.compose(SWDIO_OUT, SWCLK | SWDIO_OUT);
+
+            MpsseCmdBuilder::new()
+                .clock_bits_in(ClockEdge::Rising, BitOrder::LsbFirst, 1)
+                .set_low_data_bits(pin_value, direction)
+                .clock_bytes_out(ClockEdge::Falling, BitOrder::LsbFirst, &value.to_le_bytes())
+                .clock_bits_out(ClockEdge::Falling, BitOrder::LsbFirst, parity, 1)
+                .clock_bits_out(ClockEdge::Falling, BitOrder::LsbFirst, 0x00, 8)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn request(&self, port: Port, read: bool, address: u8) -> Result<()> {
+        let header = request_header(port, read, address);
+
+        for _ in 0..=self.wait_retries {
+            let res = self
+                .execute(|pins| {
+                    let (out_value, out_direction) = pins.compose(SWDIO_OUT, SWCLK | SWDIO_OUT);
+                    let (in_value, in_direction) = pins.compose(SWDIO_OUT, SWCLK);
+
+                    MpsseCmdBuilder::new()
+                        .set_low_data_bits(out_value, out_direction)
+                        .clock_bits_out(ClockEdge::Falling, BitOrder::LsbFirst, header, 8)
+                        .set_low_data_bits(in_value, in_direction)
+                        .clock_bits_in(ClockEdge::Rising, BitOrder::LsbFirst, 4)
+                })
+                .await?;
+            let ack = (res[0] >> 5) & 0x07;
+
+            match ack {
+                ACK_OK => return Ok(()),
+                ACK_WAIT => self.turnaround().await?,
+                ACK_FAULT => {
+                    self.turnaround().await?;
+                    let ctrl_stat = Box::pin(self.read_dp(DP_CTRL_STAT)).await?;
+                    Box::pin(self.write_dp(DP_ABORT, ABORT_CLEAR_ERRORS)).await?;
+
+                    return Err(Error::SwdFault { ctrl_stat });
+                }
+                ack => {
+                    self.line_reset().await?;
+
+                    return Err(Error::SwdAck { ack });
+                }
+            }
+        }
+
+        Err(Error::SwdWait)
+    }
+
+    async fn turnaround(&self) -> Result<()> {
+        self.execute(|pins| {
+            let (value, direction) = pins.compose(SWDIO_OUT, SWCLK | SWDIO_OUT);
+
+            MpsseCmdBuilder::new()
+                .clock_bits_in(ClockEdge::Rising, BitOrder::LsbFirst, 1)
+                .set_low_data_bits(value, direction)
+                .clock_bits_out(ClockEdge::Falling, BitOrder::LsbFirst, 0x00, 8)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn execute(
+        &self,
+        build: impl FnOnce(&mut PinBank<'_>) -> MpsseCmdBuilder,
+    ) -> Result<Vec<u8>> {
+        let mut pins = self.pins.bank(Bank::Low).await;
+        let cmd = build(&mut pins);
+
+        self.interface.execute(cmd).await
+    }
+}
+
+fn request_header(port: Port, read: bool, address: u8) -> u8 {
+    let bits = ((port == Port::Ap) as u8) | ((read as u8) << 1) | (((address >> 2) & 0x03) << 2);
+    let parity = (bits.count_ones() & 1) as u8;
+
+    0x81 | (bits << 1) | (parity << 5)
+}
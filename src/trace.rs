@@ -0,0 +1,264 @@
+use core::fmt;
+use core::time::Duration;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nusb::transfer::{ControlIn, ControlOut, ControlType, Recipient, TransferError};
+
+use crate::Result;
+
+const BLOCK_SHB: u32 = 0x0a0d_0d0a;
+const BLOCK_IDB: u32 = 0x0000_0001;
+const BLOCK_EPB: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const LINKTYPE_USBPCAP: u16 = 249;
+
+const USBPCAP_BULK_HEADER_LEN: u16 = 27;
+const USBPCAP_CONTROL_HEADER_LEN: u16 = 28;
+const USBPCAP_TRANSFER_CONTROL: u8 = 2;
+const USBPCAP_TRANSFER_BULK: u8 = 3;
+const USBPCAP_STAGE_SETUP: u8 = 0;
+const USBPCAP_STAGE_COMPLETE: u8 = 3;
+const USBPCAP_DEVICE_TO_HOST: u8 = 0x01;
+const USBPCAP_BUS: u16 = 1;
+const USBPCAP_DEVICE: u16 = 1;
+
+static CAPTURE: Mutex<Option<BufWriter<File>>> = Mutex::new(None);
+
+pub fn start_capture(path: impl AsRef<Path>) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    let mut shb = Vec::new();
+    shb.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+    shb.extend_from_slice(&1u16.to_le_bytes());
+    shb.extend_from_slice(&0u16.to_le_bytes());
+    shb.extend_from_slice(&(-1i64).to_le_bytes());
+    write_block(&mut file, BLOCK_SHB, &shb)?;
+
+    let mut idb = Vec::new();
+    idb.extend_from_slice(&LINKTYPE_USBPCAP.to_le_bytes());
+    idb.extend_from_slice(&0u16.to_le_bytes());
+    idb.extend_from_slice(&0u32.to_le_bytes());
+    write_block(&mut file, BLOCK_IDB, &idb)?;
+
+    *CAPTURE.lock().unwrap() = Some(file);
+
+    Ok(())
+}
+
+pub fn stop_capture() -> Result<()> {
+    if let Some(mut file) = CAPTURE.lock().unwrap().take() {
+        file.flush()?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn control_out(
+    interface: u8,
+    pkt: &ControlOut<'_>,
+    elapsed: Duration,
+    res: &Result<(), TransferError>,
+) {
+    tracing::trace!(
+        interface,
+        request = pkt.request,
+        value = pkt.value,
+        index = pkt.index,
+        ?elapsed,
+        status = ?res,
+        data = %Hex(pkt.data),
+        "control out"
+    );
+
+    let setup = setup_packet(
+        0x00,
+        pkt.control_type,
+        pkt.recipient,
+        pkt.request,
+        pkt.value,
+        pkt.index,
+        pkt.data.len() as u16,
+    );
+    capture_control(0x00, &setup, pkt.data, &[]);
+}
+
+pub(crate) fn control_in(
+    interface: u8,
+    pkt: &ControlIn,
+    elapsed: Duration,
+    res: &Result<Vec<u8>, TransferError>,
+) {
+    let data = res.as_deref().unwrap_or_default();
+
+    tracing::trace!(
+        interface,
+        request = pkt.request,
+        value = pkt.value,
+        index = pkt.index,
+        ?elapsed,
+        status = ?res.as_ref().map(|_| ()),
+        data = %Hex(data),
+        "control in"
+    );
+
+    let setup = setup_packet(
+        0x80,
+        pkt.control_type,
+        pkt.recipient,
+        pkt.request,
+        pkt.value,
+        pkt.index,
+        pkt.length,
+    );
+    capture_control(0x80, &setup, &[], data);
+}
+
+pub(crate) fn bulk_out(endpoint: u8, data: &[u8]) {
+    tracing::trace!(endpoint, len = data.len(), data = %Hex(data), "bulk out");
+
+    capture_bulk(endpoint, data);
+}
+
+pub(crate) fn bulk_in(endpoint: u8, data: &[u8]) {
+    tracing::trace!(endpoint, len = data.len(), data = %Hex(data), "bulk in");
+
+    capture_bulk(endpoint, data);
+}
+
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn setup_packet(
+    direction: u8,
+    control_type: ControlType,
+    recipient: Recipient,
+    request: u8,
+    value: u16,
+    index: u16,
+    length: u16,
+) -> [u8; 8] {
+    let [value_lo, value_hi] = value.to_le_bytes();
+    let [index_lo, index_hi] = index.to_le_bytes();
+    let [length_lo, length_hi] = length.to_le_bytes();
+    let request_type = direction | ((control_type as u8) << 5) | recipient as u8;
+
+    [
+        request_type,
+        request,
+        value_lo,
+        value_hi,
+        index_lo,
+        index_hi,
+        length_lo,
+        length_hi,
+    ]
+}
+
+fn capture_control(direction: u8, setup: &[u8; 8], data_out: &[u8], data_in: &[u8]) {
+    let mut request = [&setup[..], data_out].concat();
+    let header = usbpcap_header(
+        USBPCAP_CONTROL_HEADER_LEN,
+        direction,
+        0,
+        USBPCAP_TRANSFER_CONTROL,
+        request.len(),
+    );
+    request.splice(0..0, header.into_iter().chain([USBPCAP_STAGE_SETUP]));
+    capture(&request);
+
+    let mut response = usbpcap_header(
+        USBPCAP_CONTROL_HEADER_LEN,
+        direction | 0x80,
+        USBPCAP_DEVICE_TO_HOST,
+        USBPCAP_TRANSFER_CONTROL,
+        data_in.len(),
+    );
+    response.push(USBPCAP_STAGE_COMPLETE);
+    response.extend_from_slice(data_in);
+    capture(&response);
+}
+
+fn capture_bulk(endpoint: u8, data: &[u8]) {
+    let info = if endpoint & 0x80 != 0 {
+        USBPCAP_DEVICE_TO_HOST
+    } else {
+        0
+    };
+    let mut packet = usbpcap_header(
+        USBPCAP_BULK_HEADER_LEN,
+        endpoint,
+        info,
+        USBPCAP_TRANSFER_BULK,
+        data.len(),
+    );
+    packet.extend_from_slice(data);
+    capture(&packet);
+}
+
+fn usbpcap_header(len: u16, endpoint: u8, info: u8, transfer: u8, data_len: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(len as usize);
+    header.extend_from_slice(&len.to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.push(info);
+    header.extend_from_slice(&USBPCAP_BUS.to_le_bytes());
+    header.extend_from_slice(&USBPCAP_DEVICE.to_le_bytes());
+    header.push(endpoint);
+    header.push(transfer);
+    header.extend_from_slice(&(data_len as u32).to_le_bytes());
+
+    header
+}
+
+fn capture(packet: &[u8]) {
+    let mut capture = CAPTURE.lock().unwrap();
+    let Some(file) = capture.as_mut() else {
+        return;
+    };
+
+    let micros = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64;
+
+    let mut epb = Vec::with_capacity(20 + packet.len());
+    epb.extend_from_slice(&0u32.to_le_bytes());
+    epb.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+    epb.extend_from_slice(&(micros as u32).to_le_bytes());
+    epb.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+    epb.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+    epb.extend_from_slice(packet);
+
+    if let Err(err) = write_block(file, BLOCK_EPB, &epb) {
+        tracing::warn!(%err, "pcapng capture stopped");
+        *capture = None;
+    }
+}
+
+fn write_block(file: &mut impl Write, block_type: u32, body: &[u8]) -> std::io::Result<()> {
+    let padding = (4 - body.len() % 4) % 4;
+    let len = (12 + body.len() + padding) as u32;
+
+    file.write_all(&block_type.to_le_bytes())?;
+    file.write_all(&len.to_le_bytes())?;
+    file.write_all(body)?;
+    file.write_all(&[0u8; 3][..padding])?;
+    file.write_all(&len.to_le_bytes())
+}
@@ -0,0 +1,118 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+
+use nusb::transfer::{Buffer, Bulk, Completion, ControlIn, ControlOut, In, Out, TransferError};
+
+pub mod mock;
+
+pub use mock::MockFtdi;
+
+pub type TransferFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+pub trait BulkEndpoint: Send {
+    fn endpoint_address(&self) -> u8;
+    fn max_packet_size(&self) -> usize;
+    fn allocate(&mut self, len: usize) -> Buffer;
+    fn submit(&mut self, buffer: Buffer);
+    fn pending(&self) -> usize;
+    fn cancel_all(&mut self);
+    fn next_complete(&mut self) -> TransferFuture<'_, Completion>;
+}
+
+pub trait Transport: Send + Sync {
+    fn control_out<'a>(
+        &'a self,
+        pkt: ControlOut<'a>,
+        timeout: Duration,
+    ) -> TransferFuture<'a, Result<(), TransferError>>;
+    fn control_in(
+        &self,
+        pkt: ControlIn,
+        timeout: Duration,
+    ) -> TransferFuture<'_, Result<Vec<u8>, TransferError>>;
+    fn bulk_in(&self, num: u8) -> crate::Result<Box<dyn BulkEndpoint>>;
+    fn bulk_out(&self, num: u8) -> crate::Result<Box<dyn BulkEndpoint>>;
+}
+
+#[derive(Debug)]
+pub struct UsbTransport {
+    interface: nusb::Interface,
+}
+
+impl UsbTransport {
+    pub fn new(interface: nusb::Interface) -> Self {
+        UsbTransport { interface }
+    }
+}
+
+impl Transport for UsbTransport {
+    fn control_out<'a>(
+        &'a self,
+        pkt: ControlOut<'a>,
+        timeout: Duration,
+    ) -> TransferFuture<'a, Result<(), TransferError>> {
+        Box::pin(async move { self.interface.control_out(pkt, timeout).await })
+    }
+
+    fn control_in(
+        &self,
+        pkt: ControlIn,
+        timeout: Duration,
+    ) -> TransferFuture<'_, Result<Vec<u8>, TransferError>> {
+        Box::pin(async move { self.interface.control_in(pkt, timeout).await })
+    }
+
+    fn bulk_in(&self, num: u8) -> crate::Result<Box<dyn BulkEndpoint>> {
+        let ep_in = self
+            .interface
+            .endpoint::<Bulk, In>(crate::protocol::endpoint_in(num))?;
+
+        Ok(Box::new(ep_in))
+    }
+
+    fn bulk_out(&self, num: u8) -> crate::Result<Box<dyn BulkEndpoint>> {
+        let ep_out = self
+            .interface
+            .endpoint::<Bulk, Out>(crate::protocol::endpoint_out(num))?;
+
+        Ok(Box::new(ep_out))
+    }
+}
+
+macro_rules! usb_bulk_endpoint {
+    ($dir:ty) => {
+        impl BulkEndpoint for nusb::Endpoint<Bulk, $dir> {
+            fn endpoint_address(&self) -> u8 {
+                nusb::Endpoint::endpoint_address(self)
+            }
+
+            fn max_packet_size(&self) -> usize {
+                nusb::Endpoint::max_packet_size(self)
+            }
+
+            fn allocate(&mut self, len: usize) -> Buffer {
+                nusb::Endpoint::allocate(self, len)
+            }
+
+            fn submit(&mut self, buffer: Buffer) {
+                nusb::Endpoint::submit(self, buffer)
+            }
+
+            fn pending(&self) -> usize {
+                nusb::Endpoint::pending(self)
+            }
+
+            fn cancel_all(&mut self) {
+                nusb::Endpoint::cancel_all(self)
+            }
+
+            fn next_complete(&mut self) -> TransferFuture<'_, Completion> {
+                Box::pin(nusb::Endpoint::next_complete(self))
+            }
+        }
+    };
+}
+
+usb_bulk_endpoint!(In);
+usb_bulk_endpoint!(Out);
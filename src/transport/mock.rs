@@ -0,0 +1,513 @@
+use core::time::Duration;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use event_listener::Event;
+use futures_lite::future;
+use nusb::transfer::{Buffer, Completion, ControlIn, ControlOut, TransferError};
+
+use super::{BulkEndpoint, TransferFuture, Transport};
+use crate::{protocol, Bitmode, ControlRequest};
+
+const DEFAULT_MODEM_STATUS: [u8; 2] = [0x01, 0x60];
+const DEFAULT_LATENCY: Duration = Duration::from_millis(16);
+const EEPROM_WORDS: usize = 128;
+const BAD_COMMAND: u8 = 0xfa;
+
+#[derive(Debug)]
+struct MockState {
+    bitmode: u8,
+    latency: Duration,
+    modem_status: [u8; 2],
+    pins: u8,
+    low_bits: u8,
+    high_bits: u8,
+    loopback: bool,
+    rx: VecDeque<u8>,
+    tx: Vec<u8>,
+    eeprom: Vec<u16>,
+    requests: Vec<(u8, u16, u16)>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    max_packet_size: usize,
+    state: std::sync::Mutex<MockState>,
+    data_ready: Event,
+}
+
+#[derive(Clone, Debug)]
+pub struct MockFtdi {
+    shared: Arc<Shared>,
+}
+
+impl MockFtdi {
+    pub fn new(max_packet_size: usize) -> Self {
+        MockFtdi {
+            shared: Arc::new(Shared {
+                max_packet_size,
+                state: std::sync::Mutex::new(MockState {
+                    bitmode: Bitmode::Reset as u8,
+                    latency: DEFAULT_LATENCY,
+                    modem_status: DEFAULT_MODEM_STATUS,
+                    pins: 0,
+                    low_bits: 0,
+                    high_bits: 0,
+                    loopback: false,
+                    rx: VecDeque::new(),
+                    tx: Vec::new(),
+                    eeprom: vec![0xffff; EEPROM_WORDS],
+                    requests: Vec::new(),
+                }),
+                data_ready: Event::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.shared.state.lock().unwrap()
+    }
+
+    pub fn push_rx(&self, data: &[u8]) {
+        self.state().rx.extend(data);
+        self.shared.data_ready.notify(usize::MAX);
+    }
+
+    pub fn take_tx(&self) -> Vec<u8> {
+        std::mem::take(&mut self.state().tx)
+    }
+
+    pub fn set_modem_status(&self, status: [u8; 2]) {
+        self.state().modem_status = status;
+    }
+
+    pub fn set_pins(&self, pins: u8) {
+        self.state().pins = pins;
+    }
+
+    pub fn set_eeprom(&self, words: Vec<u16>) {
+        self.state().eeprom = words;
+    }
+
+    pub fn eeprom(&self) -> Vec<u16> {
+        self.state().eeprom.clone()
+    }
+
+    pub fn bitmode(&self) -> u8 {
+        self.state().bitmode
+    }
+
+    pub fn latency_timer(&self) -> Duration {
+        self.state().latency
+    }
+
+    pub fn control_requests(&self) -> Vec<(u8, u16, u16)> {
+        self.state().requests.clone()
+    }
+
+    fn write(&self, data: &[u8]) {
+        {
+            let mut state = self.state();
+            if state.bitmode == Bitmode::Mpsse as u8 {
+                state.run_mpsse(data);
+            } else {
+                state.tx.extend_from_slice(data);
+            }
+        }
+        self.shared.data_ready.notify(usize::MAX);
+    }
+
+    async fn read(&self, len: usize) -> Vec<u8> {
+        loop {
+            let listener = self.shared.data_ready.listen();
+            let latency = {
+                let mut state = self.state();
+                if !state.rx.is_empty() {
+                    return self.packetize(&mut state, len);
+                }
+                state.latency
+            };
+
+            if crate::with_timeout(latency, listener).await.is_none() {
+                return self.packetize(&mut self.state(), len);
+            }
+        }
+    }
+
+    fn packetize(&self, state: &mut MockState, len: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        loop {
+            let take = (self.shared.max_packet_size - 2)
+                .min(state.rx.len())
+                .min(len.saturating_sub(out.len() + 2));
+            out.extend_from_slice(&state.modem_status);
+            out.extend(state.rx.drain(..take));
+
+            if state.rx.is_empty() || out.len() + 2 >= len {
+                return out;
+            }
+        }
+    }
+}
+
+impl MockState {
+    fn run_mpsse(&mut self, mut cmd: &[u8]) {
+        while let Some((&opcode, rest)) = cmd.split_first() {
+            cmd = rest;
+
+            let args = match opcode {
+                0x10..=0x3f => {
+                    let bits = opcode & 0x02 != 0;
+                    let write = opcode & 0x10 != 0;
+                    let read = opcode & 0x20 != 0;
+
+                    let (count, header) = if bits {
+                        (1, 1)
+                    } else {
+                        let len = cmd
+                            .get(..2)
+                            .map_or(0, |len| u16::from_le_bytes([len[0], len[1]]) as usize + 1);
+                        (len, 2)
+                    };
+                    let data_len = if write { count } else { 0 };
+                    let end = (header + data_len).min(cmd.len());
+                    let data = &cmd[header.min(end)..end];
+
+                    if read {
+                        match (self.loopback, write) {
+                            (true, true) => self.rx.extend(data),
+                            _ => self.rx.extend(std::iter::repeat(0).take(count)),
+                        }
+                    }
+
+                    end
+                }
+                0x4a | 0x4b | 0x6a | 0x6b | 0x6e | 0x6f => {
+                    if opcode & 0x20 != 0 {
+                        self.rx.push_back(0);
+                    }
+                    2
+                }
+                0x80 => {
+                    self.low_bits = cmd.first().copied().unwrap_or_default();
+                    2
+                }
+                0x82 => {
+                    self.high_bits = cmd.first().copied().unwrap_or_default();
+                    2
+                }
+                0x81 => {
+                    self.rx.push_back(self.low_bits);
+                    0
+                }
+                0x83 => {
+                    self.rx.push_back(self.high_bits);
+                    0
+                }
+                0x84 => {
+                    self.loopback = true;
+                    0
+                }
+                0x85 => {
+                    self.loopback = false;
+                    0
+                }
+                0x86 | 0x8f | 0x9c | 0x9d | 0x9e => 2,
+                0x8e => 1,
+                0x87 | 0x88 | 0x89 | 0x8a | 0x8b | 0x8c | 0x8d | 0x94 | 0x95 | 0x96 | 0x97 => 0,
+                opcode => {
+                    self.rx.extend([BAD_COMMAND, opcode]);
+                    0
+                }
+            };
+
+            cmd = &cmd[args.min(cmd.len())..];
+        }
+    }
+}
+
+impl Transport for MockFtdi {
+    fn control_out<'a>(
+        &'a self,
+        pkt: ControlOut<'a>,
+        _timeout: Duration,
+    ) -> TransferFuture<'a, Result<(), TransferError>> {
+        let mut state = self.state();
+        state.requests.push((pkt.request, pkt.value, pkt.index));
+
+        match pkt.request {
+            r if r == ControlRequest::Reset as u8 => match pkt.value {
+                protocol::RESET_PURGE_RX => state.rx.clear(),
+                protocol::RESET_PURGE_TX => state.tx.clear(),
+                _ => {
+                    state.rx.clear();
+                    state.tx.clear();
+                }
+            },
+            r if r == ControlRequest::SetBitmode as u8 => {
+                state.bitmode = (pkt.value >> 8) as u8;
+                state.loopback = false;
+            }
+            r if r == ControlRequest::SetLatencyTimer as u8 => {
+                state.latency = Duration::from_millis(pkt.value.max(1) as u64);
+            }
+            r if r == ControlRequest::WriteEeprom as u8 => {
+                if let Some(word) = state.eeprom.get_mut(pkt.index as usize) {
+                    *word = pkt.value;
+                }
+            }
+            r if r == ControlRequest::EraseEeprom as u8 => state.eeprom.fill(0xffff),
+            _ => {}
+        }
+
+        Box::pin(future::ready(Ok(())))
+    }
+
+    fn control_in(
+        &self,
+        pkt: ControlIn,
+        _timeout: Duration,
+    ) -> TransferFuture<'_, Result<Vec<u8>, TransferError>> {
+        let mut state = self.state();
+        state.requests.push((pkt.request, pkt.value, pkt.index));
+
+        let res = match pkt.request {
+            r if r == ControlRequest::GetStatus as u8 => Ok(state.modem_status.to_vec()),
+            r if r == ControlRequest::GetLatencyTimer as u8 => {
+                Ok(vec![state.latency.as_millis() as u8])
+            }
+            r if r == ControlRequest::ReadPins as u8 => Ok(vec![state.pins]),
+            r if r == ControlRequest::ReadEeprom as u8 => {
+                let word = state.eeprom.get(pkt.index as usize).copied();
+                Ok(word.unwrap_or(0xffff).to_le_bytes().to_vec())
+            }
+            _ => Err(TransferError::Stall),
+        };
+
+        Box::pin(future::ready(res))
+    }
+
+    fn bulk_in(&self, num: u8) -> crate::Result<Box<dyn BulkEndpoint>> {
+        Ok(Box::new(MockBulkIn {
+            mock: self.clone(),
+            address: protocol::endpoint_in(num),
+            pending: VecDeque::new(),
+            cancelled: 0,
+        }))
+    }
+
+    fn bulk_out(&self, num: u8) -> crate::Result<Box<dyn BulkEndpoint>> {
+        Ok(Box::new(MockBulkOut {
+            mock: self.clone(),
+            address: protocol::endpoint_out(num),
+            completed: VecDeque::new(),
+        }))
+    }
+}
+
+struct MockBulkIn {
+    mock: MockFtdi,
+    address: u8,
+    pending: VecDeque<Buffer>,
+    cancelled: usize,
+}
+
+impl BulkEndpoint for MockBulkIn {
+    fn endpoint_address(&self) -> u8 {
+        self.address
+    }
+
+    fn max_packet_size(&self) -> usize {
+        self.mock.shared.max_packet_size
+    }
+
+    fn allocate(&mut self, len: usize) -> Buffer {
+        let mut buffer = Buffer::new(len);
+        buffer.set_requested_len(len);
+        buffer
+    }
+
+    fn submit(&mut self, buffer: Buffer) {
+        self.pending.push_back(buffer);
+    }
+
+    fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn cancel_all(&mut self) {
+        self.cancelled = self.pending.len();
+    }
+
+    fn next_complete(&mut self) -> TransferFuture<'_, Completion> {
+        Box::pin(async move {
+            let len = self
+                .pending
+                .front()
+                .expect("no pending transfers")
+                .requested_len();
+
+            if self.cancelled > 0 {
+                self.cancelled -= 1;
+                return Completion {
+                    buffer: self.pending.pop_front().unwrap(),
+                    actual_len: 0,
+                    status: Err(TransferError::Cancelled),
+                };
+            }
+
+            let data = self.mock.read(len).await;
+            let mut buffer = self.pending.pop_front().unwrap();
+            buffer.clear();
+            buffer.extend_from_slice(&data);
+
+            Completion {
+                buffer,
+                actual_len: data.len(),
+                status: Ok(()),
+            }
+        })
+    }
+}
+
+struct MockBulkOut {
+    mock: MockFtdi,
+    address: u8,
+    completed: VecDeque<Buffer>,
+}
+
+impl BulkEndpoint for MockBulkOut {
+    fn endpoint_address(&self) -> u8 {
+        self.address
+    }
+
+    fn max_packet_size(&self) -> usize {
+        self.mock.shared.max_packet_size
+    }
+
+    fn allocate(&mut self, len: usize) -> Buffer {
+        Buffer::new(len)
+    }
+
+    fn submit(&mut self, buffer: Buffer) {
+        self.mock.write(&buffer);
+        self.completed.push_back(buffer);
+    }
+
+    fn pending(&self) -> usize {
+        self.completed.len()
+    }
+
+    fn cancel_all(&mut self) {}
+
+    fn next_complete(&mut self) -> TransferFuture<'_, Completion> {
+        let buffer = self.completed.pop_front().expect("no pending transfers");
+
+        Box::pin(future::ready(Completion {
+            actual_len: buffer.len(),
+            buffer,
+            status: Ok(()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures_lite::future::block_on;
+
+    use super::MockFtdi;
+    use crate::mpsse::{BitOrder, ClockEdge, MpsseCmdBuilder, MpsseInterface};
+    use crate::{Bitmode, ControlRequest, DeviceType, Interface};
+
+    fn open(mock: &MockFtdi, device_type: DeviceType, num: u8) -> Interface {
+        Interface::with_transport(Arc::new(mock.clone()), device_type, num).unwrap()
+    }
+
+    #[test]
+    fn read_all_strips_status_bytes() {
+        let mock = MockFtdi::new(64);
+        let interface = open(&mock, DeviceType::FT232R, 0);
+        let data: Vec<u8> = (0..200).collect();
+        mock.push_rx(&data);
+
+        let mut buf = vec![0; data.len()];
+        block_on(interface.read_all(&mut buf)).unwrap();
+
+        assert_eq!(buf, data);
+    }
+
+    #[test]
+    fn read_all_across_latency_flushes() {
+        let mock = MockFtdi::new(64);
+        let interface = open(&mock, DeviceType::FT232R, 0);
+        mock.push_rx(b"ab");
+
+        let mut buf = [0; 4];
+        block_on(futures_lite::future::zip(
+            interface.read_all(&mut buf),
+            async {
+                futures_timer::Delay::new(core::time::Duration::from_millis(40)).await;
+                mock.push_rx(b"cd");
+            },
+        ))
+        .0
+        .unwrap();
+
+        assert_eq!(&buf, b"abcd");
+    }
+
+    #[test]
+    fn set_baudrate_packs_divisor_and_index() {
+        let mock = MockFtdi::new(64);
+        let interface = open(&mock, DeviceType::FT232R, 0);
+        assert_eq!(block_on(interface.set_baudrate(9600)).unwrap(), 9600);
+        assert_eq!(
+            mock.control_requests().last(),
+            Some(&(ControlRequest::SetBaudrate as u8, 0x4138, 0x0000))
+        );
+
+        let mock = MockFtdi::new(512);
+        let interface = open(&mock, DeviceType::FT2232H, 1);
+        assert_eq!(block_on(interface.set_baudrate(115_200)).unwrap(), 115_246);
+        assert_eq!(
+            mock.control_requests().last(),
+            Some(&(ControlRequest::SetBaudrate as u8, 0xc068, 0x0202))
+        );
+    }
+
+    #[test]
+    fn execute_returns_mpsse_responses() {
+        let mock = MockFtdi::new(512);
+        let interface = open(&mock, DeviceType::FT232H, 0);
+
+        let res = block_on(async {
+            interface.set_bitmode(0, Bitmode::Mpsse).await?;
+            interface
+                .execute(
+                    MpsseCmdBuilder::new()
+                        .set_low_data_bits(0x5a, 0xfb)
+                        .get_low_data_bits()
+                        .enable_loopback()
+                        .clock_bytes_inout(ClockEdge::Falling, BitOrder::MsbFirst, &[1, 2, 3]),
+                )
+                .await
+        })
+        .unwrap();
+
+        assert_eq!(res, [0x5a, 1, 2, 3]);
+        assert_eq!(mock.bitmode(), Bitmode::Mpsse as u8);
+    }
+
+    #[test]
+    fn execute_requires_mpsse_mode() {
+        let mock = MockFtdi::new(512);
+        let interface = open(&mock, DeviceType::FT232H, 0);
+
+        let res = block_on(interface.execute(MpsseCmdBuilder::new().get_low_data_bits()));
+
+        assert!(matches!(res, Err(crate::Error::WrongBitmode { .. })));
+    }
+}
@@ -0,0 +1,338 @@
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+use core::future::Future;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+use core::pin::Pin;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+use core::task::{Context, Poll};
+use std::collections::VecDeque;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::selftest::{self, SelfTestReport};
+use crate::{Interface, Result, XOFF, XON};
+
+const SOFTWARE_FLOW_CHUNK: usize = 64;
+
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+type BoxFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+
+pub struct Uart {
+    interface: Interface,
+    leftover: Vec<u8>,
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
+    read_fut: Option<BoxFuture<Vec<u8>>>,
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
+    write_fut: Option<BoxFuture<usize>>,
+}
+
+impl Uart {
+    pub fn new(interface: Interface) -> Self {
+        Uart {
+            interface,
+            leftover: Vec::new(),
+            #[cfg(any(feature = "tokio", feature = "futures-io"))]
+            read_fut: None,
+            #[cfg(any(feature = "tokio", feature = "futures-io"))]
+            write_fut: None,
+        }
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn into_inner(self) -> Interface {
+        self.interface
+    }
+
+    pub async fn selftest_echo(&mut self, len: usize, timeout: Duration) -> Result<SelfTestReport> {
+        let pattern = selftest::pattern(len);
+        self.leftover.clear();
+        self.interface.purge_rx().await?;
+
+        let start = Instant::now();
+        self.interface.write_all(pattern.clone()).await?;
+
+        let mut received = Vec::with_capacity(len);
+        while received.len() < len {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            let read = self.interface.read_some(len - received.len());
+            match crate::with_timeout(remaining, read).await {
+                Some(data) => received.extend_from_slice(&data?),
+                None => break,
+            }
+        }
+
+        let elapsed = start.elapsed();
+
+        Ok(SelfTestReport::compare(&pattern, &received, elapsed))
+    }
+
+    #[cfg(feature = "codec")]
+    pub fn into_framed<C>(self, codec: C) -> tokio_util::codec::Framed<Self, C> {
+        tokio_util::codec::Framed::new(self, codec)
+    }
+}
+
+impl core::fmt::Debug for Uart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Uart")
+            .field("interface", &self.interface)
+            .finish()
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+impl Uart {
+    fn poll_read_into(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        if !self.leftover.is_empty() {
+            let n = self.leftover.len().min(buf.len());
+            buf[..n].copy_from_slice(&self.leftover[..n]);
+            self.leftover.drain(..n);
+            return Poll::Ready(Ok(n));
+        }
+
+        let data = match self.poll_read_some(cx, buf.len()) {
+            Poll::Ready(res) => res?,
+            Poll::Pending => return Poll::Pending,
+        };
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        self.leftover.extend_from_slice(&data[n..]);
+
+        Poll::Ready(Ok(n))
+    }
+
+    #[cfg(feature = "futures-io")]
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        if self.leftover.is_empty() {
+            let max = self.interface.max_packet_size;
+            self.leftover = match self.poll_read_some(cx, max) {
+                Poll::Ready(res) => res?,
+                Poll::Pending => return Poll::Pending,
+            };
+        }
+
+        Poll::Ready(Ok(&self.leftover[..]))
+    }
+
+    fn poll_read_some(&mut self, cx: &mut Context<'_>, max: usize) -> Poll<io::Result<Vec<u8>>> {
+        let fut = self.read_fut.get_or_insert_with(|| {
+            let interface = self.interface.clone();
+            Box::pin(async move { interface.read_some(max).await })
+        });
+
+        let res = match fut.as_mut().poll(cx) {
+            Poll::Ready(res) => res,
+            Poll::Pending => return Poll::Pending,
+        };
+        self.read_fut = None;
+
+        Poll::Ready(res.map_err(io::Error::from))
+    }
+
+    fn poll_write_from(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.poll_pending_write(cx) {
+            Poll::Ready(res) => res?,
+            Poll::Pending => return Poll::Pending,
+        }
+
+        let interface = self.interface.clone();
+        let data = buf.to_vec();
+        let len = data.len();
+        let mut fut: BoxFuture<usize> = Box::pin(async move {
+            interface.write_all(data).await?;
+            Ok(len)
+        });
+
+        match fut.as_mut().poll(cx) {
+            Poll::Ready(res) => {
+                res.map_err(io::Error::from)?;
+            }
+            Poll::Pending => self.write_fut = Some(fut),
+        }
+
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_pending_write(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(fut) = self.write_fut.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        let res = match fut.as_mut().poll(cx) {
+            Poll::Ready(res) => res,
+            Poll::Pending => return Poll::Pending,
+        };
+        self.write_fut = None;
+
+        Poll::Ready(res.map(|_| ()).map_err(io::Error::from))
+    }
+}
+
+#[derive(Debug)]
+pub struct SoftwareFlowControl {
+    interface: Interface,
+    xon: u8,
+    xoff: u8,
+    paused: bool,
+    received: VecDeque<u8>,
+}
+
+impl SoftwareFlowControl {
+    pub fn new(interface: Interface) -> Self {
+        Self::with_chars(interface, XON, XOFF)
+    }
+
+    pub fn with_chars(interface: Interface, xon: u8, xoff: u8) -> Self {
+        SoftwareFlowControl {
+            interface,
+            xon,
+            xoff,
+            paused: false,
+            received: VecDeque::new(),
+        }
+    }
+
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    pub fn into_inner(self) -> Interface {
+        self.interface
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while self.received.is_empty() {
+            self.fetch(None).await?;
+        }
+
+        let n = buf.len().min(self.received.len());
+        for (dst, src) in buf.iter_mut().zip(self.received.drain(..n)) {
+            *dst = src;
+        }
+
+        Ok(n)
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> Result<()> {
+        for chunk in data.chunks(SOFTWARE_FLOW_CHUNK) {
+            self.fetch(Some(Duration::ZERO)).await?;
+            while self.paused {
+                self.fetch(None).await?;
+            }
+
+            self.interface.write_all(chunk.to_vec()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn fetch(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let mut buf = [0u8; SOFTWARE_FLOW_CHUNK];
+        let n = match timeout {
+            Some(timeout) => self.interface.read_timeout(&mut buf, timeout).await?,
+            None => self.interface.read(&mut buf).await?,
+        };
+
+        for &byte in &buf[..n] {
+            if byte == self.xon {
+                self.paused = false;
+            } else if byte == self.xoff {
+                self.paused = true;
+            } else {
+                self.received.push_back(byte);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncRead for Uart {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_read_into(cx, buf)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncBufRead for Uart {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.get_mut().poll_fill(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        let amt = amt.min(this.leftover.len());
+        this.leftover.drain(..amt);
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncWrite for Uart {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_write_from(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_pending_write(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_pending_write(cx)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for Uart {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = match self.get_mut().poll_read_into(cx, buf.initialize_unfilled()) {
+            Poll::Ready(res) => res?,
+            Poll::Pending => return Poll::Pending,
+        };
+        buf.advance(n);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for Uart {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_write_from(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_pending_write(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_pending_write(cx)
+    }
+}